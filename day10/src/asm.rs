@@ -0,0 +1,529 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    fmt::{self, Display, Write},
+};
+
+use crate::{Instruction, Operand, Register, Statement};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmErrorKind {
+    UnknownInstruction(String),
+    WrongNumberOfOperands {
+        opcode: String,
+        expected: usize,
+        found: usize,
+    },
+    ExpectedRegister(String),
+    /// A token that is neither a number nor a name.
+    InvalidValue(String),
+    InvalidName(String),
+    UndefinedSymbol(String),
+    DuplicateSymbol(String),
+    MalformedConstant,
+}
+
+/// An error in a program's source, at a line and column counting from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: AsmErrorKind,
+}
+
+impl Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+
+        match &self.kind {
+            AsmErrorKind::UnknownInstruction(opcode) => {
+                write!(f, "unknown instruction `{}`", opcode)
+            }
+            AsmErrorKind::WrongNumberOfOperands {
+                opcode,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` takes {} operand(s) but was given {}",
+                opcode, expected, found
+            ),
+            AsmErrorKind::ExpectedRegister(token) => {
+                write!(f, "expected a register but found `{}`", token)
+            }
+            AsmErrorKind::InvalidValue(token) => write!(f, "`{}` isn't a number or a name", token),
+            AsmErrorKind::InvalidName(name) => write!(f, "`{}` can't be used as a name", name),
+            AsmErrorKind::UndefinedSymbol(name) => write!(f, "`{}` hasn't been defined", name),
+            AsmErrorKind::DuplicateSymbol(name) => write!(f, "`{}` is already defined", name),
+            AsmErrorKind::MalformedConstant => {
+                write!(f, "constants should look like `const NAME = VALUE`")
+            }
+        }
+    }
+}
+
+impl Error for AsmError {}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    column: usize,
+}
+
+/// Splits a line into whitespace separated tokens, dropping any `;` comment.
+fn tokenize(line: &str) -> Vec<Token<'_>> {
+    let code = line.split(';').next().unwrap();
+    let mut tokens = vec![];
+    let mut current: Option<(usize, usize)> = None;
+
+    for (column, (idx, c)) in code.char_indices().enumerate() {
+        if c.is_whitespace() {
+            if let Some((start, start_column)) = current.take() {
+                tokens.push(Token {
+                    text: &code[start..idx],
+                    column: start_column + 1,
+                });
+            }
+        } else if current.is_none() {
+            current = Some((idx, column));
+        }
+    }
+
+    if let Some((start, start_column)) = current {
+        tokens.push(Token {
+            text: &code[start..],
+            column: start_column + 1,
+        });
+    }
+
+    tokens
+}
+
+#[derive(Debug, Default)]
+struct Symbols {
+    constants: HashMap<String, i64>,
+    /// The index of the instruction each label comes before.
+    labels: HashMap<String, usize>,
+}
+
+impl Symbols {
+    fn check_new(&self, token: &Token, name: &str) -> Result<(), (usize, AsmErrorKind)> {
+        let mut chars = name.chars();
+        let is_identifier = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        if !is_identifier || name == "const" || name.parse::<Register>().is_ok() {
+            Err((token.column, AsmErrorKind::InvalidName(name.to_string())))
+        } else if self.constants.contains_key(name) || self.labels.contains_key(name) {
+            Err((
+                token.column,
+                AsmErrorKind::DuplicateSymbol(name.to_string()),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Assembles a program, which may use these on top of the plain instruction
+/// syntax of `parse_input`:
+///
+/// - `; comments` to the end of a line,
+/// - `name:` labels before an instruction, which jumps can use in place of an
+///   offset,
+/// - `const NAME = 5` lines, which let `NAME` be used in place of a number
+///   anywhere in the program, even before the line that defines it.
+pub fn assemble(source: &str) -> Result<Vec<Statement>, AsmError> {
+    let mut symbols = Symbols::default();
+    let mut lines = vec![];
+
+    for (idx, line) in source.lines().enumerate() {
+        let line_number = idx + 1;
+        let at_line = |(column, kind)| AsmError {
+            line: line_number,
+            column,
+            kind,
+        };
+        let all_tokens = tokenize(line);
+        let mut tokens = &all_tokens[..];
+
+        if tokens.first().is_some_and(|token| token.text == "const") {
+            let (name, value) = match tokens {
+                [_, name, equals, value] if equals.text == "=" => (name, value),
+                _ => return Err(at_line((tokens[0].column, AsmErrorKind::MalformedConstant))),
+            };
+            symbols.check_new(name, name.text).map_err(at_line)?;
+            let value = value.text.parse().map_err(|_| {
+                at_line((
+                    value.column,
+                    AsmErrorKind::InvalidValue(value.text.to_string()),
+                ))
+            })?;
+            symbols.constants.insert(name.text.to_string(), value);
+            continue;
+        }
+
+        while let Some((label, rest)) = tokens.split_first() {
+            let Some(name) = label.text.strip_suffix(':') else {
+                break;
+            };
+            symbols.check_new(label, name).map_err(at_line)?;
+            symbols.labels.insert(name.to_string(), lines.len());
+            tokens = rest;
+        }
+
+        if !tokens.is_empty() {
+            lines.push((line_number, tokens.to_vec()));
+        }
+    }
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(pc, (line_number, tokens))| {
+            let instruction =
+                parse_tokens(tokens, &symbols, pc).map_err(|(column, kind)| AsmError {
+                    line: *line_number,
+                    column,
+                    kind,
+                })?;
+
+            Ok(Statement {
+                instruction,
+                line_number: *line_number,
+            })
+        })
+        .collect()
+}
+
+/// Parses a single line of plain instruction syntax, reporting the column of
+/// any error.
+pub(crate) fn parse_line(line: &str) -> Result<Instruction, (usize, AsmErrorKind)> {
+    parse_tokens(&tokenize(line), &Symbols::default(), 0)
+}
+
+fn parse_tokens(
+    tokens: &[Token],
+    symbols: &Symbols,
+    pc: usize,
+) -> Result<Instruction, (usize, AsmErrorKind)> {
+    let Some((opcode, operands)) = tokens.split_first() else {
+        return Err((1, AsmErrorKind::UnknownInstruction(String::new())));
+    };
+
+    let expect_operands = |expected: usize| {
+        if operands.len() == expected {
+            Ok(())
+        } else {
+            let column = operands.get(expected).unwrap_or(opcode).column;
+            Err((
+                column,
+                AsmErrorKind::WrongNumberOfOperands {
+                    opcode: opcode.text.to_string(),
+                    expected,
+                    found: operands.len(),
+                },
+            ))
+        }
+    };
+
+    let instruction = match opcode.text {
+        "noop" => {
+            expect_operands(0)?;
+            Instruction::Noop
+        }
+        "halt" => {
+            expect_operands(0)?;
+            Instruction::Halt
+        }
+        "set" => {
+            expect_operands(2)?;
+            Instruction::Set(register(&operands[0])?, operand(&operands[1], symbols)?)
+        }
+        "jmp" => {
+            expect_operands(1)?;
+            Instruction::Jump(offset(&operands[0], symbols, pc)?)
+        }
+        "jz" => {
+            expect_operands(2)?;
+            Instruction::JumpIfZero(register(&operands[0])?, offset(&operands[1], symbols, pc)?)
+        }
+        "jnz" => {
+            expect_operands(2)?;
+            Instruction::JumpIfNotZero(register(&operands[0])?, offset(&operands[1], symbols, pc)?)
+        }
+        text => {
+            // Arithmetic opcodes name the register they update, as in `addx`.
+            let (operation, register) = ["add", "sub", "mul"]
+                .into_iter()
+                .find_map(|operation| {
+                    let register = text.strip_prefix(operation)?.parse::<Register>().ok()?;
+                    Some((operation, register))
+                })
+                .ok_or_else(|| {
+                    (
+                        opcode.column,
+                        AsmErrorKind::UnknownInstruction(text.to_string()),
+                    )
+                })?;
+
+            expect_operands(1)?;
+            let operand = operand(&operands[0], symbols)?;
+
+            match operation {
+                "add" => Instruction::Add(register, operand),
+                "sub" => Instruction::Sub(register, operand),
+                _ => Instruction::Mul(register, operand),
+            }
+        }
+    };
+
+    Ok(instruction)
+}
+
+fn register(token: &Token) -> Result<Register, (usize, AsmErrorKind)> {
+    token.text.parse().map_err(|_| {
+        (
+            token.column,
+            AsmErrorKind::ExpectedRegister(token.text.to_string()),
+        )
+    })
+}
+
+fn value(token: &Token, symbols: &Symbols) -> Result<i64, (usize, AsmErrorKind)> {
+    if let Ok(value) = token.text.parse() {
+        return Ok(value);
+    }
+
+    if let Some(value) = symbols.constants.get(token.text) {
+        Ok(*value)
+    } else if symbols.check_new(token, token.text).is_ok() {
+        Err((
+            token.column,
+            AsmErrorKind::UndefinedSymbol(token.text.to_string()),
+        ))
+    } else {
+        Err((
+            token.column,
+            AsmErrorKind::InvalidValue(token.text.to_string()),
+        ))
+    }
+}
+
+fn operand(token: &Token, symbols: &Symbols) -> Result<Operand, (usize, AsmErrorKind)> {
+    match token.text.parse() {
+        Ok(register) => Ok(Operand::Register(register)),
+        Err(_) => value(token, symbols).map(Operand::Value),
+    }
+}
+
+/// A jump's offset, given as a number, a constant or a label.
+fn offset(token: &Token, symbols: &Symbols, pc: usize) -> Result<i64, (usize, AsmErrorKind)> {
+    match symbols.labels.get(token.text) {
+        Some(target) => Ok(*target as i64 - pc as i64),
+        None => value(token, symbols),
+    }
+}
+
+/// Writes `program` back out in the plain syntax, one instruction per line.
+pub fn disassemble(program: &[Statement]) -> String {
+    program
+        .iter()
+        .map(|statement| format!("{}\n", statement.instruction))
+        .collect()
+}
+
+/// Writes `program` out with a label at every jump target, which the jumps
+/// refer to instead of offsets, and the instructions indented beneath them.
+pub fn pretty_print(program: &[Statement]) -> String {
+    let jump_target = |pc: usize, instruction: &Instruction| {
+        let offset = match instruction {
+            Instruction::Jump(offset)
+            | Instruction::JumpIfZero(_, offset)
+            | Instruction::JumpIfNotZero(_, offset) => *offset,
+            _ => return None,
+        };
+        let target = pc as i64 + offset;
+
+        (0..=program.len() as i64)
+            .contains(&target)
+            .then_some(target as usize)
+    };
+
+    let targets: BTreeSet<_> = program
+        .iter()
+        .enumerate()
+        .filter_map(|(pc, statement)| jump_target(pc, &statement.instruction))
+        .collect();
+    let labels: HashMap<_, _> = targets
+        .iter()
+        .enumerate()
+        .map(|(idx, target)| (*target, format!("L{}", idx + 1)))
+        .collect();
+
+    let mut output = String::new();
+
+    for (pc, statement) in program.iter().enumerate() {
+        if let Some(label) = labels.get(&pc) {
+            writeln!(output, "{}:", label).unwrap();
+        }
+
+        let label = jump_target(pc, &statement.instruction).map(|target| &labels[&target]);
+        let text = match (statement.instruction, label) {
+            (Instruction::Jump(_), Some(label)) => format!("jmp {}", label),
+            (Instruction::JumpIfZero(register, _), Some(label)) => {
+                format!("jz {} {}", register, label)
+            }
+            (Instruction::JumpIfNotZero(register, _), Some(label)) => {
+                format!("jnz {} {}", register, label)
+            }
+            (instruction, _) => instruction.to_string(),
+        };
+        writeln!(output, "    {}", text).unwrap();
+    }
+
+    if let Some(label) = labels.get(&program.len()) {
+        writeln!(output, "{}:", label).unwrap();
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{parse_input, Cpu, Status};
+
+    fn instructions(program: &[Statement]) -> Vec<Instruction> {
+        program
+            .iter()
+            .map(|statement| statement.instruction)
+            .collect()
+    }
+
+    #[test]
+    fn plain_syntax_is_unchanged() {
+        let input = fs::read_to_string("test_input.txt").unwrap();
+
+        assert_eq!(assemble(&input).unwrap(), parse_input(&input));
+    }
+
+    #[test]
+    fn labels_comments_and_constants() {
+        let source = "\
+; Works out N factorial in x.
+const N = 5
+
+    set y N
+    set x 1
+loop: mulx y        ; x *= y
+    suby 1
+    jnz y loop
+    jnz x end
+    addx -1000
+end:
+";
+
+        let program = assemble(source).unwrap();
+
+        assert_eq!(
+            instructions(&program),
+            vec![
+                Instruction::Set(Register::Y, Operand::Value(5)),
+                Instruction::Set(Register::X, Operand::Value(1)),
+                Instruction::Mul(Register::X, Operand::Register(Register::Y)),
+                Instruction::Sub(Register::Y, Operand::Value(1)),
+                Instruction::JumpIfNotZero(Register::Y, -2),
+                Instruction::JumpIfNotZero(Register::X, 2),
+                Instruction::Add(Register::X, Operand::Value(-1000)),
+            ]
+        );
+        assert_eq!(program[2].line_number, 6);
+
+        let mut cpu = Cpu::new(program);
+        assert_eq!(cpu.run_with_hooks(|_, _| {}, |_, _| {}), Status::Finished);
+        assert_eq!(cpu.x(), 120);
+    }
+
+    #[test]
+    fn constants_can_be_used_before_they_are_defined() {
+        let program = assemble("addx STEP\nconst STEP = 3\n").unwrap();
+
+        assert_eq!(
+            instructions(&program),
+            vec![Instruction::Add(Register::X, Operand::Value(3))]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            ("noop\n  addq 1", 2, 3, "unknown instruction `addq`"),
+            ("addx", 1, 1, "`addx` takes 1 operand(s) but was given 0"),
+            (
+                "noop 1 2",
+                1,
+                6,
+                "`noop` takes 0 operand(s) but was given 2",
+            ),
+            ("set 1 2", 1, 5, "expected a register but found `1`"),
+            ("jmp nowhere", 1, 5, "`nowhere` hasn't been defined"),
+            ("addx 1.5", 1, 6, "`1.5` isn't a number or a name"),
+            ("a:\nnoop\na: noop", 3, 1, "`a` is already defined"),
+            ("x: noop", 1, 1, "`x` can't be used as a name"),
+            (
+                "const A 1",
+                1,
+                1,
+                "constants should look like `const NAME = VALUE`",
+            ),
+        ];
+
+        for (source, line, column, message) in cases {
+            let error = assemble(source).unwrap_err();
+
+            assert_eq!((error.line, error.column), (line, column), "{}", source);
+            assert_eq!(
+                error.to_string(),
+                format!("line {}, column {}: {}", line, column, message)
+            );
+        }
+    }
+
+    #[test]
+    fn disassemble_round_trip() {
+        let input = fs::read_to_string("test_input.txt").unwrap();
+        let program = parse_input(&input);
+
+        let text = disassemble(&program);
+
+        assert_eq!(text, format!("{}\n", input.trim_end()));
+        assert_eq!(parse_input(&text), program);
+    }
+
+    #[test]
+    fn pretty_print_round_trip() {
+        let program = parse_input("set y 3\nsuby 1\njnz y -1\njz y 2\njmp -10\nhalt");
+
+        let text = pretty_print(&program);
+
+        let expected = "    set y 3
+L1:
+    suby 1
+    jnz y L1
+    jz y L2
+    jmp -10
+L2:
+    halt
+";
+        assert_eq!(text, expected);
+        assert_eq!(
+            instructions(&assemble(&text).unwrap()),
+            instructions(&program)
+        );
+    }
+}
@@ -0,0 +1,510 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use crate::{Cpu, CrtConfig, OverflowPolicy, Register, Status, CRT};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn holds(&self, a: i64, b: i64) -> bool {
+        match self {
+            Comparison::Equal => a == b,
+            Comparison::NotEqual => a != b,
+            Comparison::Less => a < b,
+            Comparison::LessOrEqual => a <= b,
+            Comparison::Greater => a > b,
+            Comparison::GreaterOrEqual => a >= b,
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "==" => Ok(Comparison::Equal),
+            "!=" => Ok(Comparison::NotEqual),
+            "<" => Ok(Comparison::Less),
+            "<=" => Ok(Comparison::LessOrEqual),
+            ">" => Ok(Comparison::Greater),
+            ">=" => Ok(Comparison::GreaterOrEqual),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        };
+
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stops before the cycle with this number starts.
+    Cycle(usize),
+    /// Stops before the statement on this line starts.
+    Line(usize),
+    /// Stops once the comparison becomes true.
+    Register {
+        register: Register,
+        comparison: Comparison,
+        value: i64,
+    },
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Cycle(cycle) => write!(f, "cycle {}", cycle),
+            Breakpoint::Line(line) => write!(f, "line {}", line),
+            Breakpoint::Register {
+                register,
+                comparison,
+                value,
+            } => write!(f, "{} {} {}", register, comparison, value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugCommand {
+    /// Runs this many cycles, ignoring breakpoints.
+    Step(usize),
+    /// Runs until this many instructions have completed, ignoring breakpoints.
+    Next(usize),
+    /// Runs until a breakpoint is hit or the program stops.
+    Continue,
+    Break(Breakpoint),
+    /// Removes the breakpoint with this number, counting from 1.
+    Delete(usize),
+    Breakpoints,
+    Registers,
+    Crt,
+    /// Toggles showing the CRT every time the program stops.
+    Watch,
+    Quit,
+}
+
+impl FromStr for DebugCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split_line: Vec<&str> = s.split_whitespace().collect();
+        let number = |text: &str| {
+            text.parse::<usize>()
+                .map_err(|_| format!("`{}` isn't a number", text))
+        };
+
+        match split_line[..] {
+            ["step" | "s"] => Ok(DebugCommand::Step(1)),
+            ["step" | "s", count] => Ok(DebugCommand::Step(number(count)?)),
+            ["next" | "n"] => Ok(DebugCommand::Next(1)),
+            ["next" | "n", count] => Ok(DebugCommand::Next(number(count)?)),
+            ["continue" | "c"] => Ok(DebugCommand::Continue),
+            ["break" | "b", "cycle", cycle] => {
+                Ok(DebugCommand::Break(Breakpoint::Cycle(number(cycle)?)))
+            }
+            ["break" | "b", "line", line] => {
+                Ok(DebugCommand::Break(Breakpoint::Line(number(line)?)))
+            }
+            ["break" | "b", register, comparison, value] => {
+                Ok(DebugCommand::Break(Breakpoint::Register {
+                    register: register
+                        .parse()
+                        .map_err(|_| format!("`{}` isn't a register", register))?,
+                    comparison: comparison
+                        .parse()
+                        .map_err(|_| format!("`{}` isn't a comparison", comparison))?,
+                    value: value
+                        .parse()
+                        .map_err(|_| format!("`{}` isn't a number", value))?,
+                }))
+            }
+            ["delete" | "d", idx] => Ok(DebugCommand::Delete(number(idx)?)),
+            ["breakpoints" | "info"] => Ok(DebugCommand::Breakpoints),
+            ["registers" | "regs" | "r"] => Ok(DebugCommand::Registers),
+            ["crt"] => Ok(DebugCommand::Crt),
+            ["watch"] => Ok(DebugCommand::Watch),
+            ["quit" | "q"] => Ok(DebugCommand::Quit),
+            _ => Err(format!("unknown command `{}`", s.trim())),
+        }
+    }
+}
+
+/// Steps through a program on a `Cpu`, drawing the CRT from `x` as it goes.
+pub struct Debugger {
+    cpu: Cpu,
+    breakpoints: Vec<Breakpoint>,
+    /// The value of `x` during each cycle that has run.
+    register_values: Vec<i64>,
+    watch_crt: bool,
+    /// Whether `continue` has checked the breakpoints before the first cycle,
+    /// which no earlier cycle has checked for it.
+    first_cycle_checked: bool,
+}
+
+impl Debugger {
+    pub fn new(cpu: Cpu) -> Self {
+        Debugger {
+            cpu,
+            breakpoints: vec![],
+            register_values: vec![],
+            watch_crt: false,
+            first_cycle_checked: false,
+        }
+    }
+
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    /// Parses and executes one line of a debugger session, returning what it
+    /// printed, or `None` if it asked to quit. Blank lines and `#` comments do
+    /// nothing.
+    pub fn execute_line(&mut self, line: &str) -> Option<String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Some(String::new());
+        }
+
+        match line.parse::<DebugCommand>() {
+            Ok(DebugCommand::Quit) => None,
+            Ok(command) => Some(self.execute(&command)),
+            Err(message) => Some(format!("error: {}\n", message)),
+        }
+    }
+
+    pub fn execute(&mut self, command: &DebugCommand) -> String {
+        match command {
+            DebugCommand::Step(count) => {
+                for _ in 0..*count {
+                    if !self.tick() {
+                        break;
+                    }
+                }
+                self.stopped(String::new())
+            }
+            DebugCommand::Next(count) => {
+                for _ in 0..*count {
+                    while self.tick() && self.cpu.cycles_into_instruction() != 0 {}
+                }
+                self.stopped(String::new())
+            }
+            DebugCommand::Continue => {
+                let mut held = self.conditions_held();
+                let mut hit = None;
+
+                if self.cpu.num_cycles() == 0 && !self.first_cycle_checked {
+                    hit = self.hit_breakpoint(&held);
+                }
+                self.first_cycle_checked = true;
+
+                while hit.is_none() && self.tick() {
+                    hit = self.hit_breakpoint(&held);
+                    held = self.conditions_held();
+                }
+
+                let output = match hit {
+                    Some(idx) => format!("Breakpoint {}, {}\n", idx + 1, self.breakpoints[idx]),
+                    None => String::new(),
+                };
+                self.stopped(output)
+            }
+            DebugCommand::Break(breakpoint) => {
+                self.breakpoints.push(*breakpoint);
+                format!("Breakpoint {}: {}\n", self.breakpoints.len(), breakpoint)
+            }
+            DebugCommand::Delete(idx) => {
+                if (1..=self.breakpoints.len()).contains(idx) {
+                    self.breakpoints.remove(idx - 1);
+                    format!("Deleted breakpoint {}\n", idx)
+                } else {
+                    format!("error: there is no breakpoint {}\n", idx)
+                }
+            }
+            DebugCommand::Breakpoints => {
+                if self.breakpoints.is_empty() {
+                    return "No breakpoints\n".to_string();
+                }
+
+                self.breakpoints
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, breakpoint)| format!("{}: {}\n", idx + 1, breakpoint))
+                    .collect()
+            }
+            DebugCommand::Registers => {
+                let registers: Vec<_> = [Register::W, Register::X, Register::Y, Register::Z]
+                    .iter()
+                    .map(|register| format!("{} = {}", register, self.cpu.register(*register)))
+                    .collect();
+
+                format!("{}\n", registers.join(", "))
+            }
+            DebugCommand::Crt => self.crt(),
+            DebugCommand::Watch => {
+                self.watch_crt = !self.watch_crt;
+                if self.watch_crt {
+                    "Watching the CRT\n".to_string()
+                } else {
+                    "No longer watching the CRT\n".to_string()
+                }
+            }
+            DebugCommand::Quit => String::new(),
+        }
+    }
+
+    /// Runs one cycle, returning whether there was one to run.
+    fn tick(&mut self) -> bool {
+        let register_values = &mut self.register_values;
+
+        self.cpu
+            .tick_with_hooks(|_, cpu| register_values.push(cpu.x()), |_, _| {})
+            .is_some()
+    }
+
+    /// Whether each breakpoint's register comparison currently holds.
+    fn conditions_held(&self) -> Vec<bool> {
+        self.breakpoints
+            .iter()
+            .map(|breakpoint| match breakpoint {
+                Breakpoint::Register {
+                    register,
+                    comparison,
+                    value,
+                } => comparison.holds(self.cpu.register(*register), *value),
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// The first breakpoint that stops the program before its next cycle,
+    /// given which register comparisons held before the last one.
+    fn hit_breakpoint(&self, held_before: &[bool]) -> Option<usize> {
+        let statement = self.cpu.current_statement()?;
+        let held_now = self.conditions_held();
+
+        self.breakpoints
+            .iter()
+            .enumerate()
+            .position(|(idx, breakpoint)| match breakpoint {
+                Breakpoint::Cycle(cycle) => self.cpu.num_cycles() + 1 == *cycle,
+                Breakpoint::Line(line) => {
+                    self.cpu.cycles_into_instruction() == 0 && statement.line_number == *line
+                }
+                Breakpoint::Register { .. } => held_now[idx] && !held_before[idx],
+            })
+    }
+
+    /// Describes where the program has stopped, after `output`.
+    fn stopped(&self, mut output: String) -> String {
+        match (self.cpu.status(), self.cpu.current_statement()) {
+            (Status::Running, Some(statement)) => output.push_str(&format!(
+                "cycle {}, line {}: {} ({} of {})\n",
+                self.cpu.num_cycles() + 1,
+                statement.line_number,
+                statement.instruction,
+                self.cpu.cycles_into_instruction() + 1,
+                statement.instruction.cycles()
+            )),
+            (Status::Halted, _) => output.push_str(&format!(
+                "The program halted after {} cycles\n",
+                self.cpu.num_cycles()
+            )),
+            (Status::CycleLimitReached, _) => output.push_str(&format!(
+                "The program reached the cycle limit after {} cycles\n",
+                self.cpu.num_cycles()
+            )),
+            _ => output.push_str(&format!(
+                "The program finished after {} cycles\n",
+                self.cpu.num_cycles()
+            )),
+        }
+
+        if self.watch_crt {
+            output.push_str(&self.crt());
+        }
+
+        output
+    }
+
+    fn crt(&self) -> String {
+        let mut crt = CRT::with_config(CrtConfig {
+            overflow: OverflowPolicy::Clip,
+            ..Default::default()
+        })
+        .expect("The default screen should have pixels");
+        crt.draw(&self.register_values)
+            .expect("Clipped drawing should not fail");
+
+        crt.get_display()
+    }
+}
+
+/// Runs each line of `script` through the debugger until it quits or the
+/// script ends, returning a transcript of the commands and their output.
+pub fn run_script(debugger: &mut Debugger, script: &str) -> String {
+    let mut transcript = String::new();
+
+    for line in script.lines() {
+        if line.trim().is_empty() || line.trim().starts_with('#') {
+            continue;
+        }
+
+        transcript.push_str(&format!("(dbg) {}\n", line.trim()));
+        match debugger.execute_line(line) {
+            Some(output) => transcript.push_str(&output),
+            None => break,
+        }
+    }
+
+    transcript
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::parse_input;
+
+    fn example() -> Debugger {
+        let input = fs::read_to_string("test_input.txt").unwrap();
+
+        Debugger::new(Cpu::new(parse_input(&input)))
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!("s".parse(), Ok(DebugCommand::Step(1)));
+        assert_eq!("next 3".parse(), Ok(DebugCommand::Next(3)));
+        assert_eq!(
+            "b x >= -4".parse(),
+            Ok(DebugCommand::Break(Breakpoint::Register {
+                register: Register::X,
+                comparison: Comparison::GreaterOrEqual,
+                value: -4
+            }))
+        );
+        assert_eq!(
+            "break q == 1".parse::<DebugCommand>(),
+            Err("`q` isn't a register".to_string())
+        );
+        assert_eq!(
+            "jump".parse::<DebugCommand>(),
+            Err("unknown command `jump`".to_string())
+        );
+    }
+
+    #[test]
+    fn script() {
+        let script = "\
+# Check the value of x during the 20th cycle.
+break cycle 20
+continue
+registers
+step
+next
+break line 3
+delete 1
+breakpoints
+continue
+bogus
+quit
+step
+";
+
+        let expected = "\
+(dbg) break cycle 20
+Breakpoint 1: cycle 20
+(dbg) continue
+Breakpoint 1, cycle 20
+cycle 20, line 11: addx -1 (1 of 2)
+(dbg) registers
+w = 0, x = 21, y = 0, z = 0
+(dbg) step
+cycle 21, line 11: addx -1 (2 of 2)
+(dbg) next
+cycle 22, line 12: addx 5 (1 of 2)
+(dbg) break line 3
+Breakpoint 2: line 3
+(dbg) delete 1
+Deleted breakpoint 1
+(dbg) breakpoints
+1: line 3
+(dbg) continue
+The program finished after 240 cycles
+(dbg) bogus
+error: unknown command `bogus`
+(dbg) quit
+";
+
+        assert_eq!(run_script(&mut example(), script), expected);
+    }
+
+    #[test]
+    fn register_breakpoint_triggers_when_condition_becomes_true() {
+        let mut debugger = example();
+
+        debugger.execute_line("break x < 0");
+        let output = debugger.execute_line("continue").unwrap();
+        assert!(output.starts_with("Breakpoint 1, x < 0\n"));
+        assert!(debugger.cpu().x() < 0);
+        let first_stop = debugger.cpu().num_cycles();
+
+        debugger.execute_line("continue");
+        assert!(debugger.cpu().num_cycles() > first_stop + 1);
+    }
+
+    #[test]
+    fn breakpoints_on_the_first_cycle() {
+        for breakpoint in ["break cycle 1", "break line 1"] {
+            let mut debugger = example();
+
+            debugger.execute_line(breakpoint);
+
+            assert_eq!(
+                debugger.execute_line("continue").unwrap(),
+                format!(
+                    "Breakpoint 1, {}\ncycle 1, line 1: addx 15 (1 of 2)\n",
+                    &breakpoint[6..]
+                )
+            );
+            assert_eq!(
+                debugger.execute_line("continue").unwrap(),
+                "The program finished after 240 cycles\n"
+            );
+        }
+    }
+
+    #[test]
+    fn watch_crt() {
+        let mut debugger = example();
+
+        debugger.execute_line("watch");
+        let output = debugger.execute_line("step 5").unwrap();
+
+        assert!(output.contains("##..#..................................."));
+    }
+}
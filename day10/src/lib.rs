@@ -1,51 +1,433 @@
-pub trait Command {
-    fn apply(&self, machine: &mut Machine);
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    ops::{Index, IndexMut},
+    str::FromStr,
+};
+
+pub mod asm;
+pub mod debugger;
+pub mod netpbm;
+pub mod ocr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    W,
+    X,
+    Y,
+    Z,
 }
 
-#[derive(Debug)]
-struct AddCommand {
-    value: i64,
+impl FromStr for Register {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "w" => Ok(Register::W),
+            "x" => Ok(Register::X),
+            "y" => Ok(Register::Y),
+            "z" => Ok(Register::Z),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Register::W => "w",
+            Register::X => "x",
+            Register::Y => "y",
+            Register::Z => "z",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// The values of every register.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Registers([i64; 4]);
+
+impl Index<Register> for Registers {
+    type Output = i64;
+
+    fn index(&self, register: Register) -> &Self::Output {
+        &self.0[register as usize]
+    }
+}
+
+impl IndexMut<Register> for Registers {
+    fn index_mut(&mut self, register: Register) -> &mut Self::Output {
+        &mut self.0[register as usize]
+    }
+}
+
+/// A value read by an instruction, either given directly or from a register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Value(i64),
+    Register(Register),
+}
+
+impl FromStr for Operand {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<Register>() {
+            Ok(register) => Ok(Operand::Register(register)),
+            Err(_) => s.parse().map(Operand::Value).map_err(|_| ()),
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Value(value) => write!(f, "{}", value),
+            Operand::Register(register) => write!(f, "{}", register),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Noop,
+    Add(Register, Operand),
+    Sub(Register, Operand),
+    Mul(Register, Operand),
+    Set(Register, Operand),
+    /// Moves by the offset from this instruction, rather than to the next one.
+    Jump(i64),
+    JumpIfZero(Register, i64),
+    JumpIfNotZero(Register, i64),
+    Halt,
+}
+
+impl Instruction {
+    /// How many cycles the instruction takes to complete.
+    pub fn cycles(&self) -> usize {
+        match self {
+            Instruction::Noop => 1,
+            Instruction::Add(..) | Instruction::Sub(..) => 2,
+            Instruction::Mul(..) => 3,
+            Instruction::Set(..) => 1,
+            Instruction::Jump(_) => 1,
+            Instruction::JumpIfZero(..) | Instruction::JumpIfNotZero(..) => 2,
+            Instruction::Halt => 1,
+        }
+    }
 }
 
-#[derive(Debug)]
-struct NoopCommand;
+impl FromStr for Instruction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        asm::parse_line(s).map_err(|_| ())
+    }
+}
 
-impl Command for AddCommand {
-    fn apply(&self, machine: &mut Machine) {
-        machine.register_value += self.value;
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Noop => write!(f, "noop"),
+            Instruction::Add(register, operand) => write!(f, "add{} {}", register, operand),
+            Instruction::Sub(register, operand) => write!(f, "sub{} {}", register, operand),
+            Instruction::Mul(register, operand) => write!(f, "mul{} {}", register, operand),
+            Instruction::Set(register, operand) => write!(f, "set {} {}", register, operand),
+            Instruction::Jump(offset) => write!(f, "jmp {}", offset),
+            Instruction::JumpIfZero(register, offset) => write!(f, "jz {} {}", register, offset),
+            Instruction::JumpIfNotZero(register, offset) => {
+                write!(f, "jnz {} {}", register, offset)
+            }
+            Instruction::Halt => write!(f, "halt"),
+        }
     }
 }
 
-impl Command for NoopCommand {
-    fn apply(&self, _machine: &mut Machine) {}
+/// An instruction along with the line of the program it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Statement {
+    pub instruction: Instruction,
+    /// Counting from 1.
+    pub line_number: usize,
 }
 
-pub fn parse_input(input: &str) -> Vec<Box<dyn Command>> {
+/// Parses one instruction per line. Tokens can be separated by any run of
+/// whitespace and anything after a `;` is ignored, but labels and constants
+/// need `asm::assemble`.
+///
+/// Panics on a line that isn't an instruction, including a blank one.
+pub fn parse_input(input: &str) -> Vec<Statement> {
     input
         .lines()
-        .flat_map(|line| {
-            let split_line: Vec<&str> = line.split(' ').collect();
-            let result: Vec<Box<dyn Command>> = match split_line[..] {
-                ["addx", value] => vec![
-                    Box::new(NoopCommand),
-                    Box::new(AddCommand {
-                        value: value.parse::<i64>().unwrap(),
-                    }),
-                ],
-                ["noop"] => vec![Box::new(NoopCommand)],
-                _ => panic!("Unhandled input."),
-            };
-            result
+        .enumerate()
+        .map(|(idx, line)| Statement {
+            instruction: line
+                .parse()
+                .unwrap_or_else(|_| panic!("Unhandled input on line {}.", idx + 1)),
+            line_number: idx + 1,
         })
         .collect()
 }
 
-pub fn calculate_signal_strength_sum(register_values: &[i64]) -> i64 {
-    let indices: [i64; 6] = [20, 60, 100, 140, 180, 220];
-    indices
-        .iter()
-        .map(|i| i * register_values[*i as usize - 1])
-        .sum()
+/// The cycles the puzzle samples the signal strength during.
+pub const DEFAULT_SAMPLE_CYCLES: [usize; 6] = [20, 60, 100, 140, 180, 220];
+
+/// Why the signal strength couldn't be sampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleCycleError {
+    /// A sample cycle that the program didn't run for.
+    OutOfRange { cycle: usize, num_cycles: usize },
+    /// The signal strength during `cycle`, or the sum up to it, doesn't fit in an `i64`.
+    Overflow { cycle: usize },
+}
+
+impl Display for SampleCycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SampleCycleError::OutOfRange { cycle, num_cycles } => write!(
+                f,
+                "can't sample cycle {} of a program that ran for cycles 1 to {}",
+                cycle, num_cycles
+            ),
+            SampleCycleError::Overflow { cycle } => {
+                write!(f, "signal strength overflowed at cycle {}", cycle)
+            }
+        }
+    }
+}
+
+impl Error for SampleCycleError {}
+
+/// Sums the signal strength during each of `sample_cycles`, which count from 1.
+pub fn calculate_signal_strength_sum(
+    register_values: &[i64],
+    sample_cycles: &[usize],
+) -> Result<i64, SampleCycleError> {
+    sample_cycles.iter().try_fold(0i64, |sum, &cycle| {
+        let value = cycle
+            .checked_sub(1)
+            .and_then(|idx| register_values.get(idx))
+            .ok_or(SampleCycleError::OutOfRange {
+                cycle,
+                num_cycles: register_values.len(),
+            })?;
+
+        i64::try_from(cycle)
+            .ok()
+            .and_then(|cycle| cycle.checked_mul(*value))
+            .and_then(|strength| sum.checked_add(strength))
+            .ok_or(SampleCycleError::Overflow { cycle })
+    })
+}
+
+/// One clock cycle of a `Cpu`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    /// Counting from 1.
+    pub number: usize,
+    /// The statement being executed during the cycle.
+    pub statement: Statement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Running,
+    /// The program counter left the program, by running off the end or jumping.
+    Finished,
+    /// A `halt` instruction was executed.
+    Halted,
+    /// The CPU's maximum number of cycles was reached before the program ended.
+    CycleLimitReached,
+}
+
+/// Runs a program one clock cycle at a time. Each instruction takes effect at
+/// the end of its last cycle.
+pub struct Cpu {
+    program: Vec<Statement>,
+    pc: usize,
+    /// How many cycles of the instruction at `pc` have already run.
+    elapsed: usize,
+    num_cycles: usize,
+    max_cycles: Option<usize>,
+    registers: Registers,
+    status: Status,
+}
+
+impl Cpu {
+    pub fn new(program: Vec<Statement>) -> Self {
+        Cpu::with_x(program, 1)
+    }
+
+    pub fn with_x(program: Vec<Statement>, x: i64) -> Self {
+        let mut registers = Registers::default();
+        registers[Register::X] = x;
+
+        Cpu {
+            status: if program.is_empty() {
+                Status::Finished
+            } else {
+                Status::Running
+            },
+            program,
+            pc: 0,
+            elapsed: 0,
+            num_cycles: 0,
+            max_cycles: None,
+            registers,
+        }
+    }
+
+    /// Stops the program once it has run for `max_cycles`, so that it can't
+    /// loop forever.
+    pub fn with_max_cycles(mut self, max_cycles: usize) -> Self {
+        self.max_cycles = Some(max_cycles);
+        self
+    }
+
+    pub fn x(&self) -> i64 {
+        self.registers[Register::X]
+    }
+
+    pub fn register(&self, register: Register) -> i64 {
+        self.registers[register]
+    }
+
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    /// The index into the program of the statement being executed.
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// How many cycles of the current statement have already run.
+    pub fn cycles_into_instruction(&self) -> usize {
+        self.elapsed
+    }
+
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// The number of cycles that have finished.
+    pub fn num_cycles(&self) -> usize {
+        self.num_cycles
+    }
+
+    /// The statement that the next cycle will work on, if the program hasn't
+    /// stopped.
+    pub fn current_statement(&self) -> Option<&Statement> {
+        match self.status {
+            Status::Running => self.program.get(self.pc),
+            _ => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.status != Status::Running
+    }
+
+    /// Runs a single cycle, returning `None` if the program has stopped.
+    pub fn tick(&mut self) -> Option<Cycle> {
+        self.tick_with_hooks(|_, _| {}, |_, _| {})
+    }
+
+    /// Runs a single cycle, calling `during` while the cycle is in progress and
+    /// `after` once it has finished and any instruction it completed has
+    /// taken effect.
+    pub fn tick_with_hooks(
+        &mut self,
+        during: impl FnOnce(&Cycle, &Cpu),
+        after: impl FnOnce(&Cycle, &Cpu),
+    ) -> Option<Cycle> {
+        if self.status == Status::Running && Some(self.num_cycles) == self.max_cycles {
+            self.status = Status::CycleLimitReached;
+        }
+
+        let statement = *self.current_statement()?;
+        let cycle = Cycle {
+            number: self.num_cycles + 1,
+            statement,
+        };
+
+        during(&cycle, self);
+
+        self.elapsed += 1;
+        if self.elapsed == statement.instruction.cycles() {
+            self.execute(&statement.instruction);
+            self.elapsed = 0;
+        }
+        self.num_cycles += 1;
+
+        after(&cycle, self);
+
+        Some(cycle)
+    }
+
+    /// Runs the program until it stops, calling the hooks for every cycle.
+    pub fn run_with_hooks(
+        &mut self,
+        mut during: impl FnMut(&Cycle, &Cpu),
+        mut after: impl FnMut(&Cycle, &Cpu),
+    ) -> Status {
+        while self.tick_with_hooks(&mut during, &mut after).is_some() {}
+
+        self.status
+    }
+
+    fn value(&self, operand: &Operand) -> i64 {
+        match operand {
+            Operand::Value(value) => *value,
+            Operand::Register(register) => self.registers[*register],
+        }
+    }
+
+    fn execute(&mut self, instruction: &Instruction) {
+        let mut offset = 1;
+
+        match instruction {
+            Instruction::Noop => (),
+            Instruction::Add(register, operand) => {
+                self.registers[*register] =
+                    self.registers[*register].wrapping_add(self.value(operand))
+            }
+            Instruction::Sub(register, operand) => {
+                self.registers[*register] =
+                    self.registers[*register].wrapping_sub(self.value(operand))
+            }
+            Instruction::Mul(register, operand) => {
+                self.registers[*register] =
+                    self.registers[*register].wrapping_mul(self.value(operand))
+            }
+            Instruction::Set(register, operand) => self.registers[*register] = self.value(operand),
+            Instruction::Jump(jump) => offset = *jump,
+            Instruction::JumpIfZero(register, jump) => {
+                if self.registers[*register] == 0 {
+                    offset = *jump;
+                }
+            }
+            Instruction::JumpIfNotZero(register, jump) => {
+                if self.registers[*register] != 0 {
+                    offset = *jump;
+                }
+            }
+            Instruction::Halt => {
+                self.status = Status::Halted;
+                return;
+            }
+        }
+
+        match (self.pc as i64)
+            .checked_add(offset)
+            .filter(|pc| (0..self.program.len() as i64).contains(pc))
+        {
+            Some(pc) => self.pc = pc as usize,
+            None => self.status = Status::Finished,
+        }
+    }
 }
 
 pub struct Machine {
@@ -57,17 +439,16 @@ impl Machine {
         Machine { register_value: 1 }
     }
 
-    /// Applies `commands` to update the machine's register value.
+    /// Runs `program` to update the machine's register value.
     /// Returns a vector containing each register value _during_ the cycle at each index.
-    pub fn apply_commands(&mut self, commands: &[Box<dyn Command>]) -> Vec<i64> {
-        commands
-            .iter()
-            .map(|command| {
-                let res = self.register_value;
-                command.apply(self);
-                res
-            })
-            .collect()
+    pub fn apply_commands(&mut self, program: &[Statement]) -> Vec<i64> {
+        let mut cpu = Cpu::with_x(program.to_vec(), self.register_value);
+        let mut register_values = vec![];
+
+        cpu.run_with_hooks(|_, cpu| register_values.push(cpu.x()), |_, _| {});
+        self.register_value = cpu.x();
+
+        register_values
     }
 }
 
@@ -77,45 +458,166 @@ impl Default for Machine {
     }
 }
 
+/// What the CRT does with cycles after the beam has reached the last pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Starts again from the first pixel, drawing over what was there.
+    Wrap,
+    /// Ignores them.
+    Clip,
+    /// Treats them as an error.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrtConfig {
+    pub width: usize,
+    pub height: usize,
+    /// How many pixels wide the sprite is. Its position is its middle pixel,
+    /// or the left of the middle two when it's an even width.
+    pub sprite_width: usize,
+    pub overflow: OverflowPolicy,
+    pub lit: char,
+    pub unlit: char,
+}
+
+impl Default for CrtConfig {
+    fn default() -> Self {
+        CrtConfig {
+            width: 40,
+            height: 6,
+            sprite_width: 3,
+            overflow: OverflowPolicy::Error,
+            lit: '#',
+            unlit: '.',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrtError {
+    /// The configured screen has no pixels to draw on.
+    EmptyScreen { width: usize, height: usize },
+    /// There were more cycles than pixels, and the overflow policy is `Error`.
+    Overflow {
+        num_cycles: usize,
+        num_pixels: usize,
+    },
+}
+
+impl Display for CrtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrtError::EmptyScreen { width, height } => {
+                write!(f, "a {}x{} screen has no pixels", width, height)
+            }
+            CrtError::Overflow {
+                num_cycles,
+                num_pixels,
+            } => write!(
+                f,
+                "{} cycles can't be drawn on a screen of {} pixels",
+                num_cycles, num_pixels
+            ),
+        }
+    }
+}
+
+impl Error for CrtError {}
+
 pub struct CRT {
-    width: usize,
+    config: CrtConfig,
     pixels: Vec<bool>,
 }
 
 impl CRT {
-    pub fn new(width: usize, height: usize) -> Self {
-        Self {
+    pub fn new(width: usize, height: usize) -> Result<Self, CrtError> {
+        CRT::with_config(CrtConfig {
             width,
-            pixels: vec![false; width * height],
+            height,
+            ..Default::default()
+        })
+    }
+
+    /// Fails if the screen would have no pixels.
+    pub fn with_config(config: CrtConfig) -> Result<Self, CrtError> {
+        if config.width == 0 || config.height == 0 {
+            return Err(CrtError::EmptyScreen {
+                width: config.width,
+                height: config.height,
+            });
         }
+
+        Ok(Self {
+            config,
+            pixels: vec![false; config.width * config.height],
+        })
     }
 
-    pub fn draw(&mut self, register_values: &[i64]) {
-        let mut current_pixel_position: usize = 0;
-        let mut sprite_position: i64 = 1;
+    pub fn config(&self) -> &CrtConfig {
+        &self.config
+    }
 
-        for register_values_row in register_values.chunks(self.width) {
-            for val in register_values_row.iter() {
-                sprite_position = *val;
+    pub fn width(&self) -> usize {
+        self.config.width
+    }
 
-                if (sprite_position - 1..=sprite_position + 1)
-                    .contains(&((current_pixel_position as i64) % (self.width as i64)))
-                {
-                    self.pixels[current_pixel_position] = true;
-                }
+    pub fn height(&self) -> usize {
+        self.config.height
+    }
 
-                current_pixel_position += 1;
-            }
+    /// Whether each pixel is lit, row by row.
+    pub fn pixels(&self) -> &[bool] {
+        &self.pixels
+    }
+
+    /// Draws a pixel for each cycle, lit if the sprite covered it during the
+    /// cycle.
+    ///
+    /// Fails without drawing anything if there are more cycles than pixels and
+    /// the overflow policy is `Error`.
+    pub fn draw(&mut self, register_values: &[i64]) -> Result<(), CrtError> {
+        let num_pixels = self.pixels.len();
+
+        if register_values.len() > num_pixels && self.config.overflow == OverflowPolicy::Error {
+            return Err(CrtError::Overflow {
+                num_cycles: register_values.len(),
+                num_pixels,
+            });
+        }
+
+        let num_cycles = match self.config.overflow {
+            OverflowPolicy::Wrap => register_values.len(),
+            _ => register_values.len().min(num_pixels),
+        };
+        // In i128 so that sprites at the ends of the i64 range can't overflow.
+        let sprite_width = self.config.sprite_width as i128;
+
+        for (cycle_idx, sprite_position) in register_values[..num_cycles].iter().enumerate() {
+            let current_pixel_position = cycle_idx % num_pixels;
+            let column = (current_pixel_position % self.config.width) as i128;
+            let sprite_left = i128::from(*sprite_position) - (sprite_width - 1) / 2;
+
+            self.pixels[current_pixel_position] =
+                (sprite_left..sprite_left + sprite_width).contains(&column);
         }
+
+        Ok(())
     }
 
     pub fn get_display(&self) -> String {
         let mut display_string = String::new();
 
-        for row in self.pixels.chunks(self.width) {
+        for row in self.pixels.chunks(self.config.width) {
             let row = row
                 .iter()
-                .map(|is_pixel_lit| if *is_pixel_lit { '#' } else { '.' })
+                .map(|is_pixel_lit| {
+                    if *is_pixel_lit {
+                        self.config.lit
+                    } else {
+                        self.config.unlit
+                    }
+                })
                 .collect::<String>();
 
             display_string.push_str(&row);
@@ -164,8 +666,9 @@ addx -5";
         assert_eq!(register_values[179], 16);
         assert_eq!(register_values[219], 18);
 
-        let sigal_strength_sum = calculate_signal_strength_sum(&register_values);
-        assert_eq!(sigal_strength_sum, 13140)
+        let sigal_strength_sum =
+            calculate_signal_strength_sum(&register_values, &DEFAULT_SAMPLE_CYCLES);
+        assert_eq!(sigal_strength_sum, Ok(13140))
     }
 
     #[test]
@@ -176,8 +679,8 @@ addx -5";
         let mut machine = Machine::new();
         let register_values = machine.apply_commands(&commands);
 
-        let mut crt = CRT::new(40, 6);
-        crt.draw(&register_values);
+        let mut crt = CRT::new(40, 6).unwrap();
+        crt.draw(&register_values).unwrap();
 
         let expected = "\
 ##..##..##..##..##..##..##..##..##..##..
@@ -190,4 +693,313 @@ addx -5";
 
         assert_eq!(crt.get_display(), expected);
     }
+
+    #[test]
+    fn custom_sample_cycles() {
+        let input = fs::read_to_string("test_input.txt").unwrap();
+        let register_values = Machine::new().apply_commands(&parse_input(&input));
+
+        assert_eq!(
+            calculate_signal_strength_sum(&register_values, &[20, 60]),
+            Ok(20 * 21 + 60 * 19)
+        );
+        assert_eq!(calculate_signal_strength_sum(&register_values, &[]), Ok(0));
+    }
+
+    #[test]
+    fn sample_cycle_zero() {
+        assert_eq!(
+            calculate_signal_strength_sum(&[1, 1, 1], &[1, 0]),
+            Err(SampleCycleError::OutOfRange {
+                cycle: 0,
+                num_cycles: 3
+            })
+        );
+    }
+
+    #[test]
+    fn sample_cycle_past_the_end() {
+        let error = calculate_signal_strength_sum(&[1, 1, 1], &[3, 4]).unwrap_err();
+
+        assert_eq!(
+            error,
+            SampleCycleError::OutOfRange {
+                cycle: 4,
+                num_cycles: 3
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "can't sample cycle 4 of a program that ran for cycles 1 to 3"
+        );
+    }
+
+    #[test]
+    fn signal_strength_overflow() {
+        assert_eq!(
+            calculate_signal_strength_sum(&[1, i64::MAX], &[1, 2]),
+            Err(SampleCycleError::Overflow { cycle: 2 })
+        );
+        assert_eq!(
+            calculate_signal_strength_sum(&[i64::MAX, i64::MAX], &[1, 1]),
+            Err(SampleCycleError::Overflow { cycle: 1 })
+        );
+    }
+
+    fn config(width: usize, height: usize) -> CrtConfig {
+        CrtConfig {
+            width,
+            height,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sprite_width() {
+        let mut crt = CRT::with_config(CrtConfig {
+            sprite_width: 1,
+            ..config(5, 1)
+        })
+        .unwrap();
+        crt.draw(&[2; 5]).unwrap();
+        assert_eq!(crt.get_display(), "..#..\n");
+
+        let mut crt = CRT::with_config(CrtConfig {
+            sprite_width: 4,
+            ..config(5, 1)
+        })
+        .unwrap();
+        crt.draw(&[2; 5]).unwrap();
+        assert_eq!(crt.get_display(), ".####\n");
+
+        let mut crt = CRT::with_config(CrtConfig {
+            sprite_width: 0,
+            ..config(5, 1)
+        })
+        .unwrap();
+        crt.draw(&[2; 5]).unwrap();
+        assert_eq!(crt.get_display(), ".....\n");
+    }
+
+    #[test]
+    fn extreme_sprite_positions() {
+        let mut crt = CRT::with_config(CrtConfig {
+            sprite_width: usize::MAX,
+            ..config(4, 1)
+        })
+        .unwrap();
+        crt.draw(&[i64::MIN, i64::MAX, i64::MIN, i64::MAX]).unwrap();
+        assert_eq!(crt.get_display(), ".#.#\n");
+
+        let mut crt = CRT::new(4, 1).unwrap();
+        crt.draw(&[i64::MIN, i64::MAX, 1, i64::MAX]).unwrap();
+        assert_eq!(crt.get_display(), "..#.\n");
+    }
+
+    #[test]
+    fn overflow() {
+        let register_values = [0, 0, 0, 0, 2, 2];
+
+        let mut crt = CRT::with_config(CrtConfig {
+            overflow: OverflowPolicy::Wrap,
+            ..config(2, 2)
+        })
+        .unwrap();
+        crt.draw(&register_values).unwrap();
+        assert_eq!(crt.get_display(), ".#\n##\n");
+
+        let mut crt = CRT::with_config(CrtConfig {
+            overflow: OverflowPolicy::Clip,
+            ..config(2, 2)
+        })
+        .unwrap();
+        crt.draw(&register_values).unwrap();
+        assert_eq!(crt.get_display(), "##\n##\n");
+
+        let mut crt = CRT::new(2, 2).unwrap();
+        assert_eq!(
+            crt.draw(&register_values),
+            Err(CrtError::Overflow {
+                num_cycles: 6,
+                num_pixels: 4
+            })
+        );
+    }
+
+    #[test]
+    fn overflow_is_an_error_by_default() {
+        let mut crt = CRT::new(2, 2).unwrap();
+
+        let error = crt.draw(&[2; 6]).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "6 cycles can't be drawn on a screen of 4 pixels"
+        );
+        assert_eq!(crt.get_display(), "..\n..\n");
+    }
+
+    #[test]
+    fn empty_screen() {
+        assert_eq!(
+            CRT::new(0, 6).err(),
+            Some(CrtError::EmptyScreen {
+                width: 0,
+                height: 6
+            })
+        );
+        assert_eq!(
+            CRT::with_config(config(40, 0)).err(),
+            Some(CrtError::EmptyScreen {
+                width: 40,
+                height: 0
+            })
+        );
+    }
+
+    #[test]
+    fn display_characters() {
+        let mut crt = CRT::with_config(CrtConfig {
+            lit: '█',
+            unlit: ' ',
+            ..config(4, 1)
+        })
+        .unwrap();
+        crt.draw(&[0; 4]).unwrap();
+
+        assert_eq!(crt.get_display(), "██  \n");
+    }
+
+    #[test]
+    fn statements_keep_source_lines() {
+        let program = parse_input("noop\naddx 3\naddx -5");
+
+        assert_eq!(
+            program,
+            vec![
+                Statement {
+                    instruction: Instruction::Noop,
+                    line_number: 1
+                },
+                Statement {
+                    instruction: Instruction::Add(Register::X, Operand::Value(3)),
+                    line_number: 2
+                },
+                Statement {
+                    instruction: Instruction::Add(Register::X, Operand::Value(-5)),
+                    line_number: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn cpu_hooks() {
+        let mut cpu = Cpu::new(parse_input("noop\naddx 3\naddx -5"));
+        let mut during = vec![];
+        let mut after = vec![];
+
+        cpu.run_with_hooks(
+            |cycle, cpu| during.push((cycle.number, cycle.statement.line_number, cpu.x())),
+            |cycle, cpu| after.push((cycle.number, cpu.x())),
+        );
+
+        assert_eq!(
+            during,
+            vec![(1, 1, 1), (2, 2, 1), (3, 2, 1), (4, 3, 4), (5, 3, 4)]
+        );
+        assert_eq!(after, vec![(1, 1), (2, 1), (3, 4), (4, 4), (5, -1)]);
+        assert!(cpu.is_finished());
+        assert_eq!(cpu.tick(), None);
+        assert_eq!(cpu.num_cycles(), 5);
+    }
+
+    #[test]
+    fn tick_by_tick() {
+        let mut cpu = Cpu::new(parse_input("addx 2\nnoop"));
+
+        assert_eq!(cpu.current_statement().unwrap().line_number, 1);
+        cpu.tick();
+        assert_eq!((cpu.num_cycles(), cpu.x()), (1, 1));
+        assert_eq!(cpu.current_statement().unwrap().line_number, 1);
+        cpu.tick();
+        assert_eq!((cpu.num_cycles(), cpu.x()), (2, 3));
+        assert_eq!(cpu.current_statement().unwrap().line_number, 2);
+    }
+
+    #[test]
+    fn parse_instructions() {
+        let cases = [
+            ("halt", Instruction::Halt),
+            ("suby 4", Instruction::Sub(Register::Y, Operand::Value(4))),
+            (
+                "mulw z",
+                Instruction::Mul(Register::W, Operand::Register(Register::Z)),
+            ),
+            (
+                "set z -2",
+                Instruction::Set(Register::Z, Operand::Value(-2)),
+            ),
+            ("jmp -3", Instruction::Jump(-3)),
+            ("jz w 2", Instruction::JumpIfZero(Register::W, 2)),
+            ("jnz x -1", Instruction::JumpIfNotZero(Register::X, -1)),
+            (
+                "  addx\t 3 ; comment",
+                Instruction::Add(Register::X, Operand::Value(3)),
+            ),
+        ];
+
+        for (line, instruction) in cases {
+            assert_eq!(line.parse::<Instruction>(), Ok(instruction), "{}", line);
+        }
+
+        for line in [
+            "addq 1", "ad 1", "éé 1", "jmp x", "set 1 1", "noop 1", "", "; noop",
+        ] {
+            assert_eq!(line.parse::<Instruction>(), Err(()), "{}", line);
+        }
+    }
+
+    #[test]
+    fn factorial_loop() {
+        let program = parse_input(
+            "\
+set y 5
+set x 1
+mulx y
+suby 1
+jnz y -2
+halt
+addx 1000",
+        );
+        let mut cpu = Cpu::new(program);
+
+        let status = cpu.run_with_hooks(|_, _| {}, |_, _| {});
+
+        assert_eq!(status, Status::Halted);
+        assert_eq!(cpu.x(), 120);
+        assert_eq!(cpu.register(Register::Y), 0);
+        assert_eq!(cpu.num_cycles(), 38);
+    }
+
+    #[test]
+    fn max_cycles() {
+        let mut cpu = Cpu::new(parse_input("noop\njmp -1")).with_max_cycles(10);
+
+        let status = cpu.run_with_hooks(|_, _| {}, |_, _| {});
+
+        assert_eq!(status, Status::CycleLimitReached);
+        assert_eq!(cpu.num_cycles(), 10);
+        assert_eq!(cpu.tick(), None);
+    }
+
+    #[test]
+    fn jump_out_of_program() {
+        let mut cpu = Cpu::new(parse_input("jmp 2\nhalt")).with_max_cycles(1);
+
+        let status = cpu.run_with_hooks(|_, _| {}, |_, _| {});
+
+        assert_eq!(status, Status::Finished);
+        assert_eq!(cpu.num_cycles(), 1);
+    }
 }
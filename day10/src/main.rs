@@ -1,21 +1,95 @@
-use std::fs;
+use std::{
+    env, fs,
+    io::{self, BufRead, Write},
+    path::Path,
+};
 
-use day10::{calculate_signal_strength_sum, parse_input, Machine, CRT};
+use day10::{
+    calculate_signal_strength_sum,
+    debugger::{run_script, Debugger},
+    netpbm::{animation_frames, export, write_frames, Format},
+    ocr::read_display,
+    parse_input, Cpu, CrtConfig, Machine, CRT, DEFAULT_SAMPLE_CYCLES,
+};
+
+/// Stops a program being debugged from looping forever on `continue`.
+const DEBUG_MAX_CYCLES: usize = 1_000_000;
+const DEFAULT_EXPORT_SCALE: usize = 4;
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let mut export_dir = None;
+    let mut scale = DEFAULT_EXPORT_SCALE;
+    for arg in args.iter() {
+        match arg.split_once('=') {
+            Some(("--export", dir)) => export_dir = Some(Path::new(dir)),
+            Some(("--scale", value)) => scale = value.parse().expect("Scale should be a number"),
+            _ => (),
+        }
+    }
+
     let input = fs::read_to_string("input.txt").unwrap();
 
     let commands = parse_input(&input);
+
+    if let Some(idx) = args.iter().position(|arg| arg == "--debug") {
+        let mut debugger = Debugger::new(Cpu::new(commands).with_max_cycles(DEBUG_MAX_CYCLES));
+
+        match args.get(idx + 1).filter(|arg| !arg.starts_with("--")) {
+            Some(script_path) => {
+                let script = fs::read_to_string(script_path).unwrap();
+                print!("{}", run_script(&mut debugger, &script));
+            }
+            None => debug_interactively(&mut debugger),
+        }
+
+        return;
+    }
+
     let mut machine = Machine::new();
 
     let register_values = machine.apply_commands(&commands);
 
-    let sigal_strength_sum = calculate_signal_strength_sum(&register_values);
+    let sigal_strength_sum =
+        calculate_signal_strength_sum(&register_values, &DEFAULT_SAMPLE_CYCLES).unwrap();
 
     println!("The signal strength sum is {}", sigal_strength_sum);
 
-    let mut crt = CRT::new(40, 6);
-    crt.draw(&register_values);
+    let mut crt = CRT::new(40, 6).unwrap();
+    crt.draw(&register_values).unwrap();
+
+    if let Some(dir) = export_dir {
+        fs::create_dir_all(dir).unwrap();
+        for format in [Format::Pbm, Format::Pgm] {
+            let path = dir.join(format!("crt.{}", format.extension()));
+            fs::write(path, export(&crt, format, scale)).unwrap();
+        }
+
+        let frames = animation_frames(&register_values, &CrtConfig::default()).unwrap();
+        let paths = write_frames(&dir.join("frames"), &frames, Format::Pgm, scale).unwrap();
+        println!("Wrote {} frames to {}", paths.len(), dir.display());
+    }
+
+    match read_display(&crt.get_display()) {
+        Ok(text) => println!("The CRT shows {}", text),
+        Err(error) => println!("{}\n\n{}", crt.get_display(), error),
+    }
+}
+
+fn debug_interactively(debugger: &mut Debugger) {
+    let mut lines = io::stdin().lock().lines();
+
+    loop {
+        print!("(dbg) ");
+        io::stdout().flush().unwrap();
 
-    println!("{}", crt.get_display());
+        let Some(line) = lines.next() else {
+            break;
+        };
+        match debugger.execute_line(&line.unwrap()) {
+            Some(output) => print!("{}", output),
+            None => break,
+        }
+    }
 }
@@ -0,0 +1,216 @@
+use std::{
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{CrtConfig, CrtError, OverflowPolicy, CRT};
+
+/// Plain (ASCII) netpbm formats, which keep images easy to diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A bitmap with lit pixels in black, like ink on paper.
+    Pbm,
+    /// A greymap with lit pixels in white, like the CRT itself.
+    Pgm,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Pbm => "pbm",
+            Format::Pgm => "pgm",
+        }
+    }
+}
+
+/// Plain netpbm files shouldn't have lines longer than this.
+const MAX_LINE_LENGTH: usize = 70;
+const MAX_GREY: u8 = 255;
+
+/// Encodes the CRT's pixels as an image, with each pixel drawn as a
+/// `scale` by `scale` square.
+pub fn export(crt: &CRT, format: Format, scale: usize) -> String {
+    assert!(scale > 0, "The scale should be at least 1");
+
+    let (width, height) = (crt.width() * scale, crt.height() * scale);
+    let mut image = String::new();
+
+    match format {
+        Format::Pbm => writeln!(image, "P1\n{} {}", width, height).unwrap(),
+        Format::Pgm => writeln!(image, "P2\n{} {}\n{}", width, height, MAX_GREY).unwrap(),
+    }
+
+    for row in crt.pixels().chunks(crt.width()) {
+        let values: Vec<_> = row
+            .iter()
+            .flat_map(|is_pixel_lit| {
+                let value = match (format, is_pixel_lit) {
+                    (Format::Pbm, true) => "1".to_string(),
+                    (Format::Pbm, false) => "0".to_string(),
+                    (Format::Pgm, true) => MAX_GREY.to_string(),
+                    (Format::Pgm, false) => "0".to_string(),
+                };
+                vec![value; scale]
+            })
+            .collect();
+
+        let line = wrap(&values);
+        for _ in 0..scale {
+            image.push_str(&line);
+        }
+    }
+
+    image
+}
+
+/// Joins `values` with spaces, starting a new line before any would get too long.
+fn wrap(values: &[String]) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+
+    for value in values {
+        if line_length > 0 && line_length + 1 + value.len() > MAX_LINE_LENGTH {
+            text.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            text.push(' ');
+            line_length += 1;
+        }
+
+        text.push_str(value);
+        line_length += value.len();
+    }
+
+    text.push('\n');
+    text
+}
+
+/// The CRT as it looks after each cycle, drawn from the value of `x` during
+/// each cycle. Unless `config` wraps, there's no frame for cycles past the
+/// last pixel.
+pub fn animation_frames(register_values: &[i64], config: &CrtConfig) -> Result<Vec<CRT>, CrtError> {
+    let num_frames = match config.overflow {
+        OverflowPolicy::Wrap => register_values.len(),
+        _ => register_values
+            .len()
+            .min(config.width.saturating_mul(config.height)),
+    };
+
+    (1..=num_frames)
+        .map(|num_cycles| {
+            let mut crt = CRT::with_config(*config)?;
+            crt.draw(&register_values[..num_cycles])?;
+            Ok(crt)
+        })
+        .collect()
+}
+
+/// Writes each frame to `dir` as `frame-0001.pgm` and so on, returning the
+/// paths in order.
+pub fn write_frames(
+    dir: &Path,
+    frames: &[CRT],
+    format: Format,
+    scale: usize,
+) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+
+    frames
+        .iter()
+        .enumerate()
+        .map(|(idx, frame)| {
+            let path = dir.join(format!("frame-{:04}.{}", idx + 1, format.extension()));
+            fs::write(&path, export(frame, format, scale))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    fn small_crt() -> CRT {
+        let mut crt = CRT::new(3, 2).unwrap();
+        crt.draw(&[0, 0, 0, 2, 2, 2]).unwrap();
+        crt
+    }
+
+    #[test]
+    fn pbm() {
+        assert_eq!(
+            export(&small_crt(), Format::Pbm, 1),
+            "P1\n3 2\n1 1 0\n0 1 1\n"
+        );
+        assert_eq!(
+            export(&small_crt(), Format::Pbm, 2),
+            "P1\n6 4\n1 1 1 1 0 0\n1 1 1 1 0 0\n0 0 1 1 1 1\n0 0 1 1 1 1\n"
+        );
+    }
+
+    #[test]
+    fn pgm() {
+        assert_eq!(
+            export(&small_crt(), Format::Pgm, 1),
+            "P2\n3 2\n255\n255 255 0\n0 255 255\n"
+        );
+    }
+
+    #[test]
+    fn long_rows_are_wrapped() {
+        let mut crt = CRT::new(40, 6).unwrap();
+        crt.draw(&[1; 240]).unwrap();
+
+        let image = export(&crt, Format::Pgm, 3);
+
+        assert!(image.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+        let values: Vec<_> = image.split_whitespace().skip(4).collect();
+        assert_eq!(values.len(), 120 * 18);
+        assert_eq!(values[..4], ["255", "255", "255", "255"]);
+        assert_eq!(values[9], "0");
+    }
+
+    #[test]
+    fn frames() {
+        let config = CrtConfig {
+            width: 3,
+            height: 2,
+            ..CrtConfig::default()
+        };
+        let frames = animation_frames(&[0, 0, 0, 2, 2, 2, 2], &config).unwrap();
+
+        assert_eq!(frames.len(), 6);
+        assert_eq!(frames[0].get_display(), "#..\n...\n");
+        assert_eq!(frames[5].get_display(), small_crt().get_display());
+
+        let dir = env::temp_dir().join(format!("day10-frames-{}", process::id()));
+        let written = write_frames(&dir, &frames, Format::Pbm, 1)
+            .and_then(|paths| Ok((fs::read_to_string(&paths[5])?, paths)));
+        let _ = fs::remove_dir_all(&dir);
+        let (last_frame, paths) = written.unwrap();
+
+        assert_eq!(paths.len(), 6);
+        assert_eq!(paths[0], dir.join("frame-0001.pbm"));
+        assert_eq!(last_frame, export(&small_crt(), Format::Pbm, 1));
+    }
+
+    #[test]
+    fn frames_follow_config() {
+        let config = CrtConfig {
+            width: 3,
+            height: 1,
+            sprite_width: 1,
+            overflow: OverflowPolicy::Wrap,
+            ..CrtConfig::default()
+        };
+
+        let frames = animation_frames(&[1, 1, 1, 0], &config).unwrap();
+
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[2].get_display(), ".#.\n");
+        assert_eq!(frames[3].get_display(), "##.\n");
+    }
+}
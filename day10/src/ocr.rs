@@ -0,0 +1,197 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+const GLYPH_WIDTH: usize = 4;
+const GLYPH_HEIGHT: usize = 6;
+/// Each glyph is followed by a column of unlit pixels.
+const CELL_WIDTH: usize = GLYPH_WIDTH + 1;
+
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownGlyph {
+    /// Which letter cell the glyph was in, counting from 0.
+    pub position: usize,
+    /// The glyph's pixels as `#` and `.`, one row per line.
+    pub bitmap: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OcrError {
+    WrongHeight {
+        found: usize,
+    },
+    /// Some glyphs weren't in the font. `text` has a `?` in place of each one.
+    UnknownGlyphs {
+        text: String,
+        glyphs: Vec<UnknownGlyph>,
+    },
+}
+
+impl Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OcrError::WrongHeight { found } => write!(
+                f,
+                "the display should be {} rows tall, but is {}",
+                GLYPH_HEIGHT, found
+            ),
+            OcrError::UnknownGlyphs { text, glyphs } => {
+                writeln!(f, "couldn't read every glyph in {:?}", text)?;
+                for glyph in glyphs {
+                    write!(f, "\nglyph {}:\n{}", glyph.position, glyph.bitmap)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for OcrError {}
+
+/// Reads the letters off a `#`/`.` display like `CRT::get_display` returns.
+/// Blank cells become spaces, and any at the end are dropped.
+pub fn read_display(display: &str) -> Result<String, OcrError> {
+    let rows: Vec<Vec<char>> = display.lines().map(|row| row.chars().collect()).collect();
+    if rows.len() != GLYPH_HEIGHT {
+        return Err(OcrError::WrongHeight { found: rows.len() });
+    }
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut text = String::new();
+    let mut unknown = vec![];
+
+    for (position, left) in (0..width).step_by(CELL_WIDTH).enumerate() {
+        let glyph: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                (left..left + GLYPH_WIDTH)
+                    .map(|col| match row.get(col) {
+                        Some('#') => '#',
+                        _ => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+
+        if glyph.iter().all(|row| !row.contains('#')) {
+            text.push(' ');
+        } else if let Some((letter, _)) = FONT.iter().find(|(_, bitmap)| glyph == bitmap) {
+            text.push(*letter);
+        } else {
+            text.push('?');
+            unknown.push(UnknownGlyph {
+                position,
+                bitmap: glyph.iter().map(|row| format!("{}\n", row)).collect(),
+            });
+        }
+    }
+
+    let text = text.trim_end().to_string();
+
+    if unknown.is_empty() {
+        Ok(text)
+    } else {
+        Err(OcrError::UnknownGlyphs {
+            text,
+            glyphs: unknown,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{parse_input, Machine, CRT};
+
+    fn display(path: &str) -> String {
+        let input = fs::read_to_string(path).unwrap();
+        let register_values = Machine::new().apply_commands(&parse_input(&input));
+
+        let mut crt = CRT::new(40, 6).unwrap();
+        crt.draw(&register_values).unwrap();
+
+        crt.get_display()
+    }
+
+    #[test]
+    fn whole_font() {
+        let rows: Vec<String> = (0..GLYPH_HEIGHT)
+            .map(|row| {
+                FONT.iter()
+                    .map(|(_, bitmap)| format!("{}.", bitmap[row]))
+                    .collect()
+            })
+            .collect();
+
+        assert_eq!(
+            read_display(&rows.join("\n")),
+            Ok("ABCEFGHIJKLOPRSUZ".to_string())
+        );
+    }
+
+    #[test]
+    fn puzzle_input() {
+        assert_eq!(
+            read_display(&display("input.txt")),
+            Ok("EHZFZHCZ".to_string())
+        );
+    }
+
+    #[test]
+    fn blank_cells() {
+        let display = "\
+.....####.
+.....#....
+.....###..
+.....#....
+.....#....
+.....#....
+";
+
+        assert_eq!(read_display(display), Ok(" F".to_string()));
+    }
+
+    #[test]
+    fn unknown_glyphs() {
+        let error = read_display(&display("test_input.txt")).unwrap_err();
+
+        let OcrError::UnknownGlyphs { text, glyphs } = error else {
+            panic!("Expected unknown glyphs");
+        };
+        assert_eq!(text, "????????");
+        assert_eq!(glyphs.len(), 8);
+        assert_eq!(glyphs[0].position, 0);
+        assert_eq!(glyphs[0].bitmap, "##..\n###.\n####\n####\n####\n####\n");
+    }
+
+    #[test]
+    fn wrong_height() {
+        assert_eq!(
+            read_display("####\n#..."),
+            Err(OcrError::WrongHeight { found: 2 })
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// A monkey's operation, worked out from the old worry level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Const(i128),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalError {
+    DivisionByZero,
+    Overflow,
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::DivisionByZero => write!(f, "the operation divided by 0"),
+            EvalError::Overflow => write!(f, "the worry level overflowed"),
+        }
+    }
+}
+
+impl Error for EvalError {}
+
+impl Expr {
+    pub fn evaluate(&self, old: i128) -> Result<i128, EvalError> {
+        match self {
+            Expr::Old => Ok(old),
+            Expr::Const(value) => Ok(*value),
+            Expr::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.evaluate(old)?, rhs.evaluate(old)?);
+                match op {
+                    BinOp::Add => lhs.checked_add(rhs).ok_or(EvalError::Overflow),
+                    BinOp::Sub => lhs.checked_sub(rhs).ok_or(EvalError::Overflow),
+                    BinOp::Mul => lhs.checked_mul(rhs).ok_or(EvalError::Overflow),
+                    BinOp::Div if rhs == 0 => Err(EvalError::DivisionByZero),
+                    BinOp::Div => lhs.checked_div(rhs).ok_or(EvalError::Overflow),
+                }
+            }
+        }
+    }
+
+    /// Whether the expression divides by something that is 0 whatever `old` is,
+    /// like `old / 0` or `old / (2 - 2)`.
+    pub fn divides_by_zero(&self) -> bool {
+        match self {
+            Expr::Binary(op, lhs, rhs) => {
+                (*op == BinOp::Div && !rhs.uses_old() && rhs.evaluate(0) == Ok(0))
+                    || lhs.divides_by_zero()
+                    || rhs.divides_by_zero()
+            }
+            _ => false,
+        }
+    }
+
+    pub fn uses_division(&self) -> bool {
+        match self {
+            Expr::Binary(op, lhs, rhs) => {
+                *op == BinOp::Div || lhs.uses_division() || rhs.uses_division()
+            }
+            _ => false,
+        }
+    }
+
+    fn uses_old(&self) -> bool {
+        match self {
+            Expr::Old => true,
+            Expr::Const(_) => false,
+            Expr::Binary(_, lhs, rhs) => lhs.uses_old() || rhs.uses_old(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Old,
+    Number(i128),
+    Op(BinOp),
+    Open,
+    Close,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Op(BinOp::Add),
+            '-' => Token::Op(BinOp::Sub),
+            '*' => Token::Op(BinOp::Mul),
+            '/' => Token::Op(BinOp::Div),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c if c.is_ascii_alphanumeric() => {
+                let mut end = start + c.len_utf8();
+                while let Some((idx, c)) = chars.next_if(|(_, c)| c.is_ascii_alphanumeric()) {
+                    end = idx + c.len_utf8();
+                }
+
+                match &s[start..end] {
+                    "old" => Token::Old,
+                    word if word.chars().all(|c| c.is_ascii_digit()) => Token::Number(
+                        word.parse()
+                            .map_err(|_| format!("`{}` is too large", word))?,
+                    ),
+                    word => return Err(format!("unknown name `{}`", word)),
+                }
+            }
+            c => return Err(format!("unexpected `{}`", c)),
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// A recursive descent parser where `*` and `/` bind tighter than `+` and `-`,
+/// and all four associate to the left.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn binary(
+        &mut self,
+        ops: [BinOp; 2],
+        operand: fn(&mut Parser) -> Result<Expr, String>,
+    ) -> Result<Expr, String> {
+        let mut expr = operand(self)?;
+
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            if !ops.contains(&op) {
+                break;
+            }
+            self.pos += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(operand(self)?));
+        }
+
+        Ok(expr)
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        self.binary([BinOp::Add, BinOp::Sub], Parser::term)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        self.binary([BinOp::Mul, BinOp::Div], Parser::factor)
+    }
+
+    fn factor(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Old) => Ok(Expr::Old),
+            Some(Token::Number(value)) => Ok(Expr::Const(value)),
+            Some(Token::Open) => {
+                let expr = self.expr()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err("expected `)`".to_string()),
+                }
+            }
+            Some(_) => Err("expected `old`, a number or `(`".to_string()),
+            None => Err("the expression ended early".to_string()),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+
+        let expr = parser.expr()?;
+        if parser.pos < parser.tokens.len() {
+            return Err("unexpected text after the expression".to_string());
+        }
+
+        Ok(expr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluate() {
+        let cases = [
+            ("old * 19", 10, 190),
+            ("old + old", 10, 20),
+            ("old * old", 10, 100),
+            ("old - 3", 10, 7),
+            ("old / 3", 10, 3),
+            ("2 + old * 3", 10, 32),
+            ("(2 + old) * 3", 10, 36),
+            ("old - 4 - 3", 10, 3),
+            ("old / 2 / 2", 10, 2),
+        ];
+
+        for (expr, old, expected) in cases {
+            assert_eq!(
+                expr.parse::<Expr>().unwrap().evaluate(old),
+                Ok(expected),
+                "{}",
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn evaluation_errors() {
+        let cases = [
+            ("old / (old - old)", 10, EvalError::DivisionByZero),
+            ("old * old", i128::MAX / 2, EvalError::Overflow),
+            ("old + 1", i128::MAX, EvalError::Overflow),
+            ("old - 1", i128::MIN, EvalError::Overflow),
+            ("(0 - 1) * old / (0 - 1)", i128::MIN, EvalError::Overflow),
+        ];
+
+        for (expr, old, error) in cases {
+            assert_eq!(
+                expr.parse::<Expr>().unwrap().evaluate(old),
+                Err(error),
+                "{}",
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn divides_by_zero() {
+        let cases = [
+            ("old / 0", true),
+            ("old / (2 - 2)", true),
+            ("(old + 1 / 0) * 2", true),
+            ("old / 2", false),
+            ("old / (old - old)", false),
+            ("0 / old", false),
+        ];
+
+        for (expr, expected) in cases {
+            assert_eq!(
+                expr.parse::<Expr>().unwrap().divides_by_zero(),
+                expected,
+                "{}",
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn tree() {
+        assert_eq!(
+            "old * old + 1".parse(),
+            Ok(Expr::Binary(
+                BinOp::Add,
+                Box::new(Expr::Binary(
+                    BinOp::Mul,
+                    Box::new(Expr::Old),
+                    Box::new(Expr::Old)
+                )),
+                Box::new(Expr::Const(1))
+            ))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            ("old % 2", "unexpected `%`"),
+            ("new * 2", "unknown name `new`"),
+            ("old *", "the expression ended early"),
+            ("(old + 1", "expected `)`"),
+            ("old 2", "unexpected text after the expression"),
+            ("* 2", "expected `old`, a number or `(`"),
+        ];
+
+        for (expr, message) in cases {
+            assert_eq!(expr.parse::<Expr>(), Err(message.to_string()), "{}", expr);
+        }
+    }
+}
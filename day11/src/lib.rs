@@ -1,30 +1,24 @@
-use std::{cmp::Reverse, collections::HashMap};
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display},
+};
 
-use regex::Regex;
+use expr::{EvalError, Expr};
 
+pub mod expr;
+pub mod parse;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Monkey {
     items: Vec<i128>,
-    operation: OperationFunction,
+    operation: Expr,
     test: TestFunction,
     num_inspections: u128,
 }
 
-enum OperationFunction {
-    Add(i128),
-    MulBy(i128),
-    Square,
-}
-
-impl OperationFunction {
-    fn apply(&self, old: i128) -> i128 {
-        match self {
-            OperationFunction::Add(val) => old + val,
-            OperationFunction::MulBy(val) => old * val,
-            OperationFunction::Square => old * old,
-        }
-    }
-}
-
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct TestFunction {
     divisor: i128,
     true_branch_idx: usize,
@@ -42,83 +36,20 @@ impl TestFunction {
 }
 
 pub fn parse_input(input: &str) -> Vec<Monkey> {
-    let starting_items_re = Regex::new(r"(\d+)").unwrap();
-    let operation_re = Regex::new(r"new = old (\*|\+) (\d+|old)").unwrap();
-    let test_divisor_re = Regex::new(r"Test: divisible by (\d+)").unwrap();
-    let true_branch_idx_re = Regex::new(r"If true: throw to monkey (\d+)").unwrap();
-    let false_branch_idx_re = Regex::new(r"If false: throw to monkey (\d+)").unwrap();
-
-    input
-        .lines()
-        .collect::<Vec<_>>()
-        .chunks(7)
-        .map(|group| {
-            let items: Vec<_> = starting_items_re
-                .find_iter(group[1])
-                .map(|mat| mat.as_str().parse::<i128>().unwrap())
-                .collect();
-
-            let operation_re_caps = operation_re.captures(group[2]).unwrap();
-
-            let operation = get_operation_function(
-                operation_re_caps.get(1).unwrap().as_str(),
-                operation_re_caps.get(2).unwrap().as_str(),
-            );
-
-            let divisor = test_divisor_re
-                .captures(group[3])
-                .unwrap()
-                .get(1)
-                .unwrap()
-                .as_str()
-                .parse::<i128>()
-                .unwrap();
-
-            let true_branch_idx = true_branch_idx_re
-                .captures(group[4])
-                .unwrap()
-                .get(1)
-                .unwrap()
-                .as_str()
-                .parse::<usize>()
-                .unwrap();
-
-            let false_branch_idx = false_branch_idx_re
-                .captures(group[5])
-                .unwrap()
-                .get(1)
-                .unwrap()
-                .as_str()
-                .parse::<usize>()
-                .unwrap();
-
-            let test = TestFunction {
-                divisor,
-                true_branch_idx,
-                false_branch_idx,
-            };
-
-            Monkey {
-                items,
-                operation,
-                test,
-                num_inspections: 0,
-            }
-        })
-        .collect()
-}
-
-fn get_operation_function(operator: &str, operand: &str) -> OperationFunction {
-    match [operator, operand] {
-        ["*", "old"] => OperationFunction::Square,
-        ["*", val] => OperationFunction::MulBy(val.parse::<i128>().unwrap()),
-        ["+", val] => OperationFunction::Add(val.parse::<i128>().unwrap()),
-        _ => panic!(),
-    }
+    parse::parse_monkeys(input).unwrap_or_else(|error| panic!("{}", error))
 }
 
 pub trait Strategy {
-    fn calculate_new_item(&self, monkey: &Monkey, item: i128, lcm: i128) -> i128;
+    /// `lcm` is the product of the test divisors, which worry levels can be
+    /// reduced modulo without changing any test, or `None` when an operation
+    /// divides and reducing would change what it gives, or the product doesn't
+    /// fit in an `i128`.
+    fn calculate_new_item(
+        &self,
+        monkey: &Monkey,
+        item: i128,
+        lcm: Option<i128>,
+    ) -> Result<i128, EvalError>;
     fn name(&self) -> String;
 }
 
@@ -126,8 +57,13 @@ pub trait Strategy {
 pub struct Part1Strategy;
 
 impl Strategy for Part1Strategy {
-    fn calculate_new_item(&self, monkey: &Monkey, item: i128, _lcm: i128) -> i128 {
-        monkey.operation.apply(item) / 3
+    fn calculate_new_item(
+        &self,
+        monkey: &Monkey,
+        item: i128,
+        _lcm: Option<i128>,
+    ) -> Result<i128, EvalError> {
+        Ok(monkey.operation.evaluate(item)? / 3)
     }
 
     fn name(&self) -> String {
@@ -135,12 +71,24 @@ impl Strategy for Part1Strategy {
     }
 }
 
+/// Keeps worry levels in range by reducing them modulo the test divisors.
+///
+/// That isn't sound once any operation divides, so then worry levels grow
+/// unchecked and, with an operation like `old * old`, soon fail with
+/// `EvalError::Overflow`.
 #[derive(Clone, Copy)]
 pub struct Part2Strategy;
 
 impl Strategy for Part2Strategy {
-    fn calculate_new_item(&self, monkey: &Monkey, item: i128, lcm: i128) -> i128 {
-        monkey.operation.apply(item) % lcm
+    fn calculate_new_item(
+        &self,
+        monkey: &Monkey,
+        item: i128,
+        lcm: Option<i128>,
+    ) -> Result<i128, EvalError> {
+        let new_item = monkey.operation.evaluate(item)?;
+
+        Ok(lcm.map_or(new_item, |lcm| new_item % lcm))
     }
 
     fn name(&self) -> String {
@@ -148,35 +96,106 @@ impl Strategy for Part2Strategy {
     }
 }
 
-pub fn process_monkeys(monkeys: &mut Vec<Monkey>, num_rounds: usize, strategy: impl Strategy) {
-    let lcm: i128 = monkeys.iter().map(|m| m.test.divisor).product();
+/// A monkey whose operation failed on one of its items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InspectionError {
+    pub monkey: usize,
+    pub item: i128,
+    pub error: EvalError,
+}
+
+impl Display for InspectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "monkey {} couldn't inspect an item with worry level {}: {}",
+            self.monkey, self.item, self.error
+        )
+    }
+}
+
+impl Error for InspectionError {}
+
+/// Plays `num_rounds` rounds, stopping at the first item a monkey's operation
+/// fails on and leaving the monkeys part of the way through that round, with
+/// that item still at the front of its monkey's list.
+pub fn process_monkeys(
+    monkeys: &mut [Monkey],
+    num_rounds: usize,
+    strategy: impl Strategy,
+) -> Result<(), InspectionError> {
+    let lcm = if monkeys.iter().any(|m| m.operation.uses_division()) {
+        None
+    } else {
+        monkeys
+            .iter()
+            .try_fold(1i128, |lcm, m| lcm.checked_mul(m.test.divisor))
+    };
     let mut items_to_pass: HashMap<usize, Vec<i128>> = HashMap::new();
 
+    let result = play_rounds(monkeys, num_rounds, &strategy, lcm, &mut items_to_pass);
+
+    for (idx, mut items) in items_to_pass {
+        monkeys[idx].items.append(&mut items);
+    }
+
+    result
+}
+
+fn play_rounds(
+    monkeys: &mut [Monkey],
+    num_rounds: usize,
+    strategy: &impl Strategy,
+    lcm: Option<i128>,
+    items_to_pass: &mut HashMap<usize, Vec<i128>>,
+) -> Result<(), InspectionError> {
     for _ in 0..num_rounds {
         for (idx, monkey) in monkeys.iter_mut().enumerate() {
             if let Some(mut items_to_update) = items_to_pass.remove(&idx) {
                 monkey.items.append(&mut items_to_update);
             }
 
-            for item in monkey.items.iter() {
-                let new_item = strategy.calculate_new_item(monkey, *item, lcm);
-
-                let new_monkey_idx = monkey.test.apply(new_item);
-
-                items_to_pass
-                    .entry(new_monkey_idx)
-                    .and_modify(|v| v.push(new_item))
-                    .or_insert_with(|| vec![new_item]);
-            }
+            let result = monkey
+                .items
+                .iter()
+                .enumerate()
+                .try_for_each(|(num_inspected, item)| {
+                    let new_item =
+                        strategy
+                            .calculate_new_item(monkey, *item, lcm)
+                            .map_err(|error| {
+                                (
+                                    num_inspected,
+                                    InspectionError {
+                                        monkey: idx,
+                                        item: *item,
+                                        error,
+                                    },
+                                )
+                            })?;
+
+                    let new_monkey_idx = monkey.test.apply(new_item);
+
+                    items_to_pass
+                        .entry(new_monkey_idx)
+                        .and_modify(|v| v.push(new_item))
+                        .or_insert_with(|| vec![new_item]);
+
+                    Ok(())
+                });
+
+            let num_inspected = match result {
+                Ok(()) => monkey.items.len(),
+                Err((num_inspected, _)) => num_inspected,
+            };
+            monkey.num_inspections += num_inspected as u128;
+            monkey.items.drain(..num_inspected);
 
-            monkey.num_inspections += monkey.items.len() as u128;
-            monkey.items.clear();
+            result.map_err(|(_, error)| error)?;
         }
     }
 
-    for (idx, items) in items_to_pass {
-        monkeys[idx].items = items;
-    }
+    Ok(())
 }
 
 pub fn calculate_monkey_business(monkeys: &[Monkey]) -> i128 {
@@ -194,7 +213,7 @@ mod tests {
     fn example_part_1() {
         let input = fs::read_to_string("test_input.txt").unwrap();
         let mut monkeys = parse_input(&input);
-        process_monkeys(&mut monkeys, 20, Part1Strategy);
+        process_monkeys(&mut monkeys, 20, Part1Strategy).unwrap();
 
         assert_eq!(monkeys[0].items, vec![10, 12, 14, 26, 34]);
         assert_eq!(monkeys[0].num_inspections, 101);
@@ -215,8 +234,89 @@ mod tests {
     fn example_part_2() {
         let input = fs::read_to_string("test_input.txt").unwrap();
         let mut monkeys = parse_input(&input);
-        process_monkeys(&mut monkeys, 10000, Part2Strategy);
+        process_monkeys(&mut monkeys, 10000, Part2Strategy).unwrap();
 
         assert_eq!(calculate_monkey_business(&monkeys), 2713310158);
     }
+
+    #[test]
+    fn part_2_with_division() {
+        struct Unreduced;
+
+        impl Strategy for Unreduced {
+            fn calculate_new_item(
+                &self,
+                monkey: &Monkey,
+                item: i128,
+                _lcm: Option<i128>,
+            ) -> Result<i128, EvalError> {
+                monkey.operation.evaluate(item)
+            }
+
+            fn name(&self) -> String {
+                String::from("Unreduced")
+            }
+        }
+
+        let input = fs::read_to_string("test_input.txt")
+            .unwrap()
+            .replace("old * old", "old * 7 / 5");
+        let mut monkeys = parse_input(&input);
+        let mut unreduced = monkeys.clone();
+
+        process_monkeys(&mut monkeys, 20, Part2Strategy).unwrap();
+        process_monkeys(&mut unreduced, 20, Unreduced).unwrap();
+
+        assert_eq!(monkeys, unreduced);
+    }
+
+    #[test]
+    fn part_2_with_division_overflows() {
+        let input = fs::read_to_string("test_input.txt")
+            .unwrap()
+            .replace("old + 3", "old * 7 / 5");
+        let mut monkeys = parse_input(&input);
+
+        let error = process_monkeys(&mut monkeys, 10000, Part2Strategy).unwrap_err();
+
+        assert_eq!(error.error, EvalError::Overflow);
+    }
+
+    #[test]
+    fn huge_divisors() {
+        let input = fs::read_to_string("test_input.txt")
+            .unwrap()
+            .replace("divisible by 23", &format!("divisible by {}", i128::MAX));
+        let mut monkeys = parse_input(&input);
+
+        process_monkeys(&mut monkeys, 1, Part2Strategy).unwrap();
+
+        let inspections: Vec<_> = monkeys.iter().map(|m| m.num_inspections).collect();
+        assert_eq!(inspections, vec![2, 4, 3, 6]);
+    }
+
+    #[test]
+    fn failed_inspection() {
+        let input = fs::read_to_string("test_input.txt")
+            .unwrap()
+            .replace("old + 6", "old / (old - 74)");
+        let mut monkeys = parse_input(&input);
+
+        let error = process_monkeys(&mut monkeys, 20, Part1Strategy).unwrap_err();
+
+        assert_eq!(
+            error,
+            InspectionError {
+                monkey: 1,
+                item: 74,
+                error: EvalError::DivisionByZero
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "monkey 1 couldn't inspect an item with worry level 74: the operation divided by 0"
+        );
+        assert_eq!(monkeys[1].items[0], 74);
+        assert_eq!(monkeys.iter().map(|m| m.items.len()).sum::<usize>(), 10);
+    }
 }
@@ -12,7 +12,7 @@ fn main() {
 fn run(num_rounds: usize, strategy: impl Strategy + Copy) {
     let input = fs::read_to_string("input.txt").unwrap();
     let mut monkeys = parse_input(&input);
-    process_monkeys(&mut monkeys, num_rounds, strategy);
+    process_monkeys(&mut monkeys, num_rounds, strategy).unwrap_or_else(|error| panic!("{}", error));
 
     let monkey_business = calculate_monkey_business(&monkeys);
 
@@ -0,0 +1,348 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+use crate::{expr::Expr, Monkey, TestFunction};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A line that should have started a monkey's block.
+    ExpectedHeader(String),
+    UnexpectedMonkeyNumber {
+        expected: usize,
+        found: String,
+    },
+    UnknownField(String),
+    DuplicateField(&'static str),
+    MissingField(&'static str),
+    /// A field that didn't start the way it should, such as `Test` without
+    /// `divisible by`.
+    MalformedField {
+        field: &'static str,
+        expected: &'static str,
+    },
+    InvalidNumber(String),
+    InvalidOperation(String),
+    /// An operation that divides by 0 whatever the old worry level is.
+    OperationDividesByZero,
+    DivisibleByZero,
+    UnknownMonkey(usize),
+}
+
+/// An error in the block of monkey `monkey`, at a line counting from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub monkey: usize,
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "monkey {}, line {}: ", self.monkey, self.line)?;
+
+        match &self.kind {
+            ParseErrorKind::ExpectedHeader(line) => {
+                write!(f, "expected `Monkey {}:` but found `{}`", self.monkey, line)
+            }
+            ParseErrorKind::UnexpectedMonkeyNumber { expected, found } => {
+                write!(f, "expected monkey {} but found `{}`", expected, found)
+            }
+            ParseErrorKind::UnknownField(line) => write!(f, "unknown field in `{}`", line),
+            ParseErrorKind::DuplicateField(field) => write!(f, "`{}` is given twice", field),
+            ParseErrorKind::MissingField(field) => write!(f, "`{}` is missing", field),
+            ParseErrorKind::MalformedField { field, expected } => {
+                write!(f, "`{}` should be followed by `{}`", field, expected)
+            }
+            ParseErrorKind::InvalidNumber(text) => write!(f, "`{}` isn't a number", text),
+            ParseErrorKind::InvalidOperation(message) => {
+                write!(f, "invalid operation: {}", message)
+            }
+            ParseErrorKind::OperationDividesByZero => {
+                write!(f, "the operation always divides by 0")
+            }
+            ParseErrorKind::DivisibleByZero => write!(f, "the test can't divide by 0"),
+            ParseErrorKind::UnknownMonkey(target) => {
+                write!(f, "there is no monkey {} to throw to", target)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+const STARTING_ITEMS: &str = "Starting items";
+const OPERATION: &str = "Operation";
+const TEST: &str = "Test";
+const IF_TRUE: &str = "If true";
+const IF_FALSE: &str = "If false";
+
+/// The fields of one monkey's block, as they're found.
+struct Block {
+    header_line: usize,
+    items: Option<Vec<i128>>,
+    operation: Option<Expr>,
+    divisor: Option<i128>,
+    /// The monkey to throw to, and the line it was given on.
+    if_true: Option<(usize, usize)>,
+    if_false: Option<(usize, usize)>,
+}
+
+fn set<T>(slot: &mut Option<T>, field: &'static str, value: T) -> Result<(), ParseErrorKind> {
+    if slot.is_some() {
+        return Err(ParseErrorKind::DuplicateField(field));
+    }
+
+    *slot = Some(value);
+    Ok(())
+}
+
+fn number<T: std::str::FromStr>(text: &str) -> Result<T, ParseErrorKind> {
+    let text = text.trim();
+    text.parse()
+        .map_err(|_| ParseErrorKind::InvalidNumber(text.to_string()))
+}
+
+fn strip<'a>(
+    value: &'a str,
+    field: &'static str,
+    expected: &'static str,
+) -> Result<&'a str, ParseErrorKind> {
+    value
+        .trim()
+        .strip_prefix(expected)
+        .ok_or(ParseErrorKind::MalformedField { field, expected })
+}
+
+impl Block {
+    fn new(header_line: usize) -> Self {
+        Block {
+            header_line,
+            items: None,
+            operation: None,
+            divisor: None,
+            if_true: None,
+            if_false: None,
+        }
+    }
+
+    fn add_field(&mut self, line: &str, line_number: usize) -> Result<(), ParseErrorKind> {
+        let Some((field, value)) = line.split_once(':') else {
+            return Err(ParseErrorKind::UnknownField(line.to_string()));
+        };
+
+        match field {
+            STARTING_ITEMS => {
+                let items = value
+                    .split(',')
+                    .filter(|item| !item.trim().is_empty())
+                    .map(number)
+                    .collect::<Result<_, _>>()?;
+                set(&mut self.items, STARTING_ITEMS, items)
+            }
+            OPERATION => {
+                let expr: Expr = strip(value, OPERATION, "new =")?
+                    .parse()
+                    .map_err(ParseErrorKind::InvalidOperation)?;
+                if expr.divides_by_zero() {
+                    return Err(ParseErrorKind::OperationDividesByZero);
+                }
+                set(&mut self.operation, OPERATION, expr)
+            }
+            TEST => {
+                let divisor = number(strip(value, TEST, "divisible by")?)?;
+                if divisor == 0 {
+                    return Err(ParseErrorKind::DivisibleByZero);
+                }
+                set(&mut self.divisor, TEST, divisor)
+            }
+            IF_TRUE => {
+                let target = number(strip(value, IF_TRUE, "throw to monkey")?)?;
+                set(&mut self.if_true, IF_TRUE, (target, line_number))
+            }
+            IF_FALSE => {
+                let target = number(strip(value, IF_FALSE, "throw to monkey")?)?;
+                set(&mut self.if_false, IF_FALSE, (target, line_number))
+            }
+            _ => Err(ParseErrorKind::UnknownField(line.to_string())),
+        }
+    }
+
+    fn into_monkey(self, idx: usize, num_monkeys: usize) -> Result<Monkey, ParseError> {
+        let error = |line, kind| ParseError {
+            monkey: idx,
+            line,
+            kind,
+        };
+        let missing = |field| error(self.header_line, ParseErrorKind::MissingField(field));
+        let target = |branch: Option<(usize, usize)>, field| {
+            let (target, line) = branch.ok_or_else(|| missing(field))?;
+            if target >= num_monkeys {
+                return Err(error(line, ParseErrorKind::UnknownMonkey(target)));
+            }
+            Ok(target)
+        };
+
+        Ok(Monkey {
+            items: self.items.clone().ok_or_else(|| missing(STARTING_ITEMS))?,
+            operation: self.operation.clone().ok_or_else(|| missing(OPERATION))?,
+            test: TestFunction {
+                divisor: self.divisor.ok_or_else(|| missing(TEST))?,
+                true_branch_idx: target(self.if_true, IF_TRUE)?,
+                false_branch_idx: target(self.if_false, IF_FALSE)?,
+            },
+            num_inspections: 0,
+        })
+    }
+}
+
+/// Parses each monkey's block. Blocks start at their `Monkey N:` header, and
+/// blank lines, indentation and line endings don't matter. The fields within
+/// a block may come in any order.
+pub fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, ParseError> {
+    let mut blocks: Vec<Block> = vec![];
+
+    for (idx, line) in input.lines().enumerate() {
+        let line_number = idx + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix("Monkey") {
+            let expected = blocks.len();
+            let found = header.trim().strip_suffix(':').map(str::trim);
+            if found.and_then(|found| found.parse().ok()) != Some(expected) {
+                return Err(ParseError {
+                    monkey: expected,
+                    line: line_number,
+                    kind: ParseErrorKind::UnexpectedMonkeyNumber {
+                        expected,
+                        found: line.to_string(),
+                    },
+                });
+            }
+
+            blocks.push(Block::new(line_number));
+            continue;
+        }
+
+        let monkey = blocks.len().saturating_sub(1);
+        let error = |kind| ParseError {
+            monkey,
+            line: line_number,
+            kind,
+        };
+
+        match blocks.last_mut() {
+            Some(block) => block.add_field(line, line_number).map_err(error)?,
+            None => return Err(error(ParseErrorKind::ExpectedHeader(line.to_string()))),
+        }
+    }
+
+    let num_monkeys = blocks.len();
+
+    blocks
+        .into_iter()
+        .enumerate()
+        .map(|(idx, block)| block.into_monkey(idx, num_monkeys))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{calculate_monkey_business, parse_input, process_monkeys, Part1Strategy};
+
+    fn example() -> String {
+        fs::read_to_string("test_input.txt").unwrap()
+    }
+
+    #[test]
+    fn line_endings_and_spacing() {
+        let expected = parse_input(&example());
+
+        let windows = example().replace('\n', "\r\n");
+        assert_eq!(parse_monkeys(&windows).unwrap(), expected);
+
+        let squashed = example().replace("\n\n", "\n");
+        assert_eq!(parse_monkeys(&squashed).unwrap(), expected);
+
+        let unindented: String = example()
+            .lines()
+            .map(|l| format!("{}\n", l.trim()))
+            .collect();
+        assert_eq!(parse_monkeys(&unindented).unwrap(), expected);
+    }
+
+    #[test]
+    fn general_operations() {
+        let input = example()
+            .replace("old * 19", "old + old")
+            .replace("old + 6", "(old - 1) / 2");
+        let mut monkeys = parse_monkeys(&input).unwrap();
+
+        assert_eq!(monkeys[0].operation.evaluate(79), Ok(158));
+        assert_eq!(monkeys[1].operation.evaluate(79), Ok(39));
+
+        process_monkeys(&mut monkeys, 20, Part1Strategy).unwrap();
+        assert!(calculate_monkey_business(&monkeys) > 0);
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            (
+                example().replace("old * old", "old % old"),
+                "monkey 2, line 17: invalid operation: unexpected `%`",
+            ),
+            (
+                example().replace("Monkey 1:", "Monkey 4:"),
+                "monkey 1, line 8: expected monkey 1 but found `Monkey 4:`",
+            ),
+            (
+                example().replace("  Test: divisible by 19\n", ""),
+                "monkey 1, line 8: `Test` is missing",
+            ),
+            (
+                example().replace("throw to monkey 3", "throw to monkey 7"),
+                "monkey 0, line 6: there is no monkey 7 to throw to",
+            ),
+            (
+                example().replace("79, 98", "79, lots"),
+                "monkey 0, line 2: `lots` isn't a number",
+            ),
+            (
+                example().replace("old + 6", "old / 0"),
+                "monkey 1, line 10: the operation always divides by 0",
+            ),
+            (
+                example().replace("old + 6", "old / (3 - 3)"),
+                "monkey 1, line 10: the operation always divides by 0",
+            ),
+            (
+                example().replace("divisible by 13", "divisible by 0"),
+                "monkey 2, line 18: the test can't divide by 0",
+            ),
+            (
+                example().replace("Test: divisible by 17", "Test: even"),
+                "monkey 3, line 25: `Test` should be followed by `divisible by`",
+            ),
+            (
+                example().replace("Operation: new = old + 3", "Mood: grumpy"),
+                "monkey 3, line 24: unknown field in `Mood: grumpy`",
+            ),
+            (
+                format!("hello\n{}", example()),
+                "monkey 0, line 1: expected `Monkey 0:` but found `hello`",
+            ),
+        ];
+
+        for (input, message) in cases {
+            assert_eq!(parse_monkeys(&input).unwrap_err().to_string(), message);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::ops::Range;
+
+use crate::{find_marker_end, MESSAGE_MARKER_SIZE, PACKET_MARKER_SIZE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramingRule {
+    /// Every payload is exactly this many chars long.
+    FixedLength(usize),
+    /// The payload length is encoded in the `width` chars following the marker,
+    /// as big-endian base 26 digits where `a` is 0 and `z` is 25.
+    LengthPrefix { width: usize },
+    /// The payload runs until the start of the next marker, or the end of the stream.
+    UntilNextMarker,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub marker: Range<usize>,
+    pub payload: Range<usize>,
+    pub data: String,
+    /// False if the stream ended before the payload was fully read.
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FramingError {
+    InvalidLengthPrefix {
+        offset: usize,
+        found: char,
+    },
+    /// The payload starting at `offset` would end past `usize::MAX`.
+    LengthOverflow {
+        offset: usize,
+    },
+}
+
+pub struct Decoder {
+    window_size: usize,
+    rule: FramingRule,
+}
+
+impl Decoder {
+    pub fn new(window_size: usize, rule: FramingRule) -> Self {
+        Decoder { window_size, rule }
+    }
+
+    pub fn packets(rule: FramingRule) -> Self {
+        Decoder::new(PACKET_MARKER_SIZE, rule)
+    }
+
+    pub fn messages(rule: FramingRule) -> Self {
+        Decoder::new(MESSAGE_MARKER_SIZE, rule)
+    }
+
+    pub fn decode(&self, input: &str) -> Result<Vec<Frame>, FramingError> {
+        let chars = input.chars().collect::<Vec<char>>();
+        let mut frames = vec![];
+        let mut pos = 0;
+
+        while let Some(marker_end) = find_marker_end(&chars, pos, self.window_size) {
+            let marker = marker_end - self.window_size..marker_end;
+
+            let (payload, next_pos) = match self.rule {
+                FramingRule::FixedLength(len) => {
+                    let end = checked_end(marker_end, &[len])?;
+                    (marker_end..end, end)
+                }
+                FramingRule::LengthPrefix { width } => {
+                    let payload_start = checked_end(marker_end, &[width])?;
+                    let prefix_end = payload_start.min(chars.len());
+                    let len = decode_length_prefix(&chars[marker_end..prefix_end], marker_end)?;
+                    let end = checked_end(marker_end, &[width, len])?;
+                    (payload_start..end, end)
+                }
+                FramingRule::UntilNextMarker => {
+                    match find_marker_end(&chars, marker_end, self.window_size) {
+                        Some(next_marker_end) => {
+                            let end = next_marker_end - self.window_size;
+                            (marker_end..end, end)
+                        }
+                        None => (marker_end..chars.len(), chars.len()),
+                    }
+                }
+            };
+
+            let complete = payload.end <= chars.len();
+            let payload = payload.start.min(chars.len())..payload.end.min(chars.len());
+
+            frames.push(Frame {
+                marker,
+                data: chars[payload.clone()].iter().collect(),
+                payload,
+                complete,
+            });
+
+            if !complete {
+                break;
+            }
+
+            pos = next_pos;
+        }
+
+        Ok(frames)
+    }
+}
+
+/// Adds `lengths` to `offset`, failing if the result doesn't fit in a `usize`.
+fn checked_end(offset: usize, lengths: &[usize]) -> Result<usize, FramingError> {
+    lengths
+        .iter()
+        .try_fold(offset, |end, len| end.checked_add(*len))
+        .ok_or(FramingError::LengthOverflow { offset })
+}
+
+fn decode_length_prefix(prefix: &[char], offset: usize) -> Result<usize, FramingError> {
+    prefix
+        .iter()
+        .enumerate()
+        .try_fold(0usize, |len, (idx, c)| match c {
+            'a'..='z' => len
+                .checked_mul(26)
+                .and_then(|len| len.checked_add(*c as usize - 'a' as usize))
+                .ok_or(FramingError::LengthOverflow { offset }),
+            _ => Err(FramingError::InvalidLengthPrefix {
+                offset: offset + idx,
+                found: *c,
+            }),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7)]
+    #[case("bvwbjplbgvbhsrlpgdmjqwftvncz", 5)]
+    #[case("nppdvjthqldpwncqszvftbrmjlhg", 6)]
+    #[case("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10)]
+    #[case("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11)]
+    fn first_frame_starts_after_packet_marker_case(#[case] line: &str, #[case] expected: usize) {
+        let frames = Decoder::packets(FramingRule::FixedLength(1))
+            .decode(line)
+            .unwrap();
+
+        assert_eq!(frames[0].marker, expected - 4..expected);
+        assert_eq!(frames[0].payload.start, expected);
+    }
+
+    #[test]
+    fn fixed_length() {
+        let frames = Decoder::packets(FramingRule::FixedLength(3))
+            .decode("mjqjpqmgbljsphdztnvjfqwrcgsmlb")
+            .unwrap();
+
+        let payloads: Vec<_> = frames
+            .iter()
+            .map(|f| (f.payload.clone(), f.data.as_str(), f.complete))
+            .collect();
+
+        assert_eq!(
+            payloads,
+            vec![
+                (7..10, "gbl", true),
+                (14..17, "dzt", true),
+                (21..24, "qwr", true),
+                (28..30, "lb", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn length_prefix() {
+        // Marker "jpqm" ends at 7, the prefix "g" encodes a length of 6.
+        let frames = Decoder::packets(FramingRule::LengthPrefix { width: 1 })
+            .decode("mjqjpqmgbljsphdztnvjfqwrcgsmlb")
+            .unwrap();
+
+        assert_eq!(frames[0].marker, 3..7);
+        assert_eq!(frames[0].payload, 8..14);
+        assert_eq!(frames[0].data, "bljsph");
+        assert!(frames[0].complete);
+    }
+
+    #[test]
+    fn length_prefix_invalid_digit() {
+        let result = Decoder::packets(FramingRule::LengthPrefix { width: 1 }).decode("abcd1efgh");
+
+        assert_eq!(
+            result,
+            Err(FramingError::InvalidLengthPrefix {
+                offset: 4,
+                found: '1'
+            })
+        );
+    }
+
+    #[test]
+    fn length_overflow() {
+        let result = Decoder::packets(FramingRule::LengthPrefix { width: 14 })
+            .decode("abcdzzzzzzzzzzzzzzefgh");
+        assert_eq!(result, Err(FramingError::LengthOverflow { offset: 4 }));
+
+        let result = Decoder::packets(FramingRule::FixedLength(usize::MAX)).decode("abcdefgh");
+        assert_eq!(result, Err(FramingError::LengthOverflow { offset: 4 }));
+    }
+
+    #[rstest]
+    #[case("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 19, 30)]
+    #[case("bvwbjplbgvbhsrlpgdmjqwftvncz", 23, 28)]
+    #[case("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 26, 32)]
+    fn until_next_marker_messages_case(
+        #[case] line: &str,
+        #[case] marker_end: usize,
+        #[case] len: usize,
+    ) {
+        let frames = Decoder::messages(FramingRule::UntilNextMarker)
+            .decode(line)
+            .unwrap();
+
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].marker, marker_end - 14..marker_end);
+        assert_eq!(frames[0].payload, marker_end..len);
+        assert_eq!(frames[0].data, &line[marker_end..]);
+    }
+
+    #[test]
+    fn until_next_marker_packets() {
+        let frames = Decoder::packets(FramingRule::UntilNextMarker)
+            .decode("aabcdeeffghij")
+            .unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].marker, 1..5);
+        assert_eq!(frames[0].data, "eef");
+        assert_eq!(frames[1].marker, 8..12);
+        assert_eq!(frames[1].data, "j");
+    }
+}
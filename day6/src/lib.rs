@@ -1,22 +1,35 @@
 use std::collections::HashSet;
 
+pub mod framing;
+
+pub const PACKET_MARKER_SIZE: usize = 4;
+pub const MESSAGE_MARKER_SIZE: usize = 14;
+
 pub fn find_marker_index(input: &str, window_size: usize) -> usize {
-    for (idx, group) in input
-        .chars()
-        .collect::<Vec<char>>()
-        .windows(window_size)
-        .enumerate()
-    {
+    let chars = input.chars().collect::<Vec<char>>();
+
+    find_marker_end(&chars, 0, window_size).expect("Could not find marker index.")
+}
+
+/// Returns the index just past the first window of `window_size` distinct chars
+/// that starts at or after `start`.
+pub(crate) fn find_marker_end(chars: &[char], start: usize, window_size: usize) -> Option<usize> {
+    if start >= chars.len() {
+        return None;
+    }
+
+    for (idx, group) in chars[start..].windows(window_size).enumerate() {
         let mut set: HashSet<char> = HashSet::new();
         for c in group.iter() {
             set.insert(*c);
         }
 
         if set.len() == window_size {
-            return idx + window_size;
+            return Some(start + idx + window_size);
         }
     }
-    panic!("Could not find marker index.");
+
+    None
 }
 
 #[cfg(test)]
@@ -0,0 +1,252 @@
+use std::collections::HashSet;
+
+use crate::tree::{DirId, FileSystem};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CleanupPlan {
+    pub dirs: Vec<DirId>,
+    pub space_freed: u128,
+}
+
+/// Picks a set of non-nested directories whose deletion leaves at least
+/// `required_space` unused, while deleting as few bytes as possible.
+///
+/// A protected directory can't be deleted, and neither can anything inside it or
+/// any of its ancestors. Returns `None` if no such set exists.
+pub fn plan_cleanup(
+    fs: &FileSystem,
+    max_fs_space: u128,
+    required_space: u128,
+    protected: &HashSet<DirId>,
+) -> Option<CleanupPlan> {
+    let total_used_space = fs.dir(fs.root()).size();
+    let total_unused_space = max_fs_space.saturating_sub(total_used_space);
+    let target = required_space.saturating_sub(total_unused_space);
+
+    if target == 0 {
+        return Some(CleanupPlan {
+            dirs: vec![],
+            space_freed: 0,
+        });
+    }
+
+    let dirs = Preorder::new(fs, protected);
+
+    // Sweeping backwards through the pre-order, the amounts that can be freed
+    // using only the directories from position idx onwards only ever grow. Each
+    // amount is kept once, in order, with the position of the directory that
+    // first reached it; the rest of that plan is the amount minus its size.
+    // Amounts that reach the target are never worth extending, so only the
+    // smallest of them is kept.
+    let mut reachable = vec![(0, dirs.len())];
+    let mut merged = vec![];
+    let mut best: Option<(u128, usize)> = None;
+
+    for idx in (0..dirs.len()).rev() {
+        if !dirs.deletable[idx] {
+            continue;
+        }
+
+        let size = dirs.sizes[idx];
+        let mut existing = reachable.iter().copied().peekable();
+        merged.clear();
+
+        for (rest, position) in reachable.iter().copied() {
+            if position < dirs.subtree_end[idx] {
+                continue;
+            }
+
+            let freed = rest + size;
+            if freed >= target {
+                if best.is_none_or(|(best_freed, _)| freed < best_freed) {
+                    best = Some((freed, idx));
+                }
+                break;
+            }
+
+            while let Some(entry) = existing.next_if(|(amount, _)| *amount < freed) {
+                merged.push(entry);
+            }
+            if existing.peek().is_none_or(|(amount, _)| *amount != freed) {
+                merged.push((freed, idx));
+            }
+        }
+
+        merged.extend(existing);
+        std::mem::swap(&mut reachable, &mut merged);
+    }
+
+    let (space_freed, mut idx) = best?;
+
+    let mut chosen = vec![];
+    let mut remaining = space_freed;
+    while idx < dirs.len() {
+        chosen.push(dirs.ids[idx]);
+        remaining -= dirs.sizes[idx];
+        let rest = reachable.binary_search_by_key(&remaining, |(amount, _)| *amount);
+        idx = reachable[rest.unwrap()].1;
+    }
+
+    Some(CleanupPlan {
+        dirs: chosen,
+        space_freed,
+    })
+}
+
+/// The directories in pre-order, so that every subtree occupies a contiguous
+/// range of positions.
+struct Preorder {
+    ids: Vec<DirId>,
+    sizes: Vec<u128>,
+    deletable: Vec<bool>,
+    subtree_end: Vec<usize>,
+}
+
+impl Preorder {
+    fn new(fs: &FileSystem, protected: &HashSet<DirId>) -> Self {
+        let mut preorder = Preorder {
+            ids: vec![],
+            sizes: vec![],
+            deletable: vec![],
+            subtree_end: vec![],
+        };
+
+        preorder.visit(fs, fs.root(), false, protected);
+
+        preorder
+    }
+
+    /// Returns whether the subtree of `id` contains a protected directory.
+    fn visit(
+        &mut self,
+        fs: &FileSystem,
+        id: DirId,
+        inside_protected: bool,
+        protected: &HashSet<DirId>,
+    ) -> bool {
+        let idx = self.ids.len();
+        let is_protected = inside_protected || protected.contains(&id);
+
+        self.ids.push(id);
+        self.sizes.push(fs.dir(id).size());
+        self.deletable.push(!is_protected);
+        self.subtree_end.push(idx + 1);
+
+        let mut contains_protected = is_protected;
+        for child in fs.dir(id).dirs() {
+            contains_protected |= self.visit(fs, child, is_protected, protected);
+        }
+
+        self.deletable[idx] &= !contains_protected;
+        self.subtree_end[idx] = self.ids.len();
+
+        contains_protected
+    }
+
+    fn len(&self) -> usize {
+        self.ids.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{find_smallest_dir_to_delete, parse_input};
+
+    fn three_dirs() -> (FileSystem, [DirId; 3]) {
+        let mut fs = FileSystem::new();
+        let root = fs.root();
+        let x = fs.add_dir(root, "x");
+        let y = fs.add_dir(root, "y");
+        let z = fs.add_dir(root, "z");
+        fs.add_file(x, "a", 5_000_000);
+        fs.add_file(y, "b", 4_000_000);
+        fs.add_file(z, "c", 10_000_000);
+        fs.add_file(root, "d", 1_000_000);
+        fs.compute_sizes();
+        (fs, [x, y, z])
+    }
+
+    #[test]
+    fn example_matches_single_directory() {
+        let input =
+            fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
+        let file_system = parse_input(&input);
+
+        let plan = plan_cleanup(&file_system, 70000000, 30000000, &HashSet::new()).unwrap();
+        let dir = find_smallest_dir_to_delete(&file_system, 70000000, 30000000);
+
+        assert_eq!(plan.dirs, vec![dir]);
+        assert_eq!(plan.space_freed, 24933642);
+    }
+
+    #[test]
+    fn combines_directories() {
+        let (file_system, [x, y, _]) = three_dirs();
+
+        let plan = plan_cleanup(&file_system, 30_000_000, 18_000_000, &HashSet::new()).unwrap();
+
+        assert_eq!(plan.dirs, vec![x, y]);
+        assert_eq!(plan.space_freed, 9_000_000);
+    }
+
+    #[test]
+    fn respects_protected_directories() {
+        let (file_system, [x, _, z]) = three_dirs();
+
+        let plan = plan_cleanup(&file_system, 30_000_000, 18_000_000, &HashSet::from([x])).unwrap();
+
+        assert_eq!(plan.dirs, vec![z]);
+        assert_eq!(plan.space_freed, 10_000_000);
+
+        let root = file_system.root();
+        assert_eq!(
+            plan_cleanup(&file_system, 30_000_000, 18_000_000, &HashSet::from([root])),
+            None
+        );
+    }
+
+    #[test]
+    fn multi_gigabyte_directories() {
+        let mut fs = FileSystem::new();
+        let root = fs.root();
+        let media = fs.add_dir(root, "media");
+        let films = fs.add_dir(media, "films");
+        let photos = fs.add_dir(media, "photos");
+        let backups = fs.add_dir(root, "backups");
+        fs.add_file(films, "a.mkv", 6_000_000_000);
+        fs.add_file(photos, "b.raw", 3_500_000_000);
+        fs.add_file(media, "index.db", 1_000_000_000);
+        fs.add_file(backups, "c.tar", 5_000_000_000);
+        fs.add_file(backups, "d.tar", 40_000_000_000_000);
+        fs.compute_sizes();
+
+        let plan = plan_cleanup(&fs, 40_029_500_000_000, 23_000_000_000, &HashSet::new()).unwrap();
+
+        assert_eq!(plan.dirs, vec![films, photos]);
+        assert_eq!(plan.space_freed, 9_500_000_000);
+
+        let plan = plan_cleanup(
+            &fs,
+            40_029_500_000_000,
+            23_000_000_000,
+            &HashSet::from([films]),
+        )
+        .unwrap();
+
+        assert_eq!(plan.dirs, vec![backups]);
+        assert_eq!(plan.space_freed, 40_005_000_000_000);
+    }
+
+    #[test]
+    fn nothing_to_delete() {
+        let (file_system, _) = three_dirs();
+
+        let plan = plan_cleanup(&file_system, 30_000_000, 5_000_000, &HashSet::new()).unwrap();
+
+        assert_eq!(plan.dirs, vec![]);
+        assert_eq!(plan.space_freed, 0);
+    }
+}
@@ -1,127 +1,240 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::rc::Rc;
-
-#[derive(Debug, Clone)]
-pub struct Directory {
-    pub path: String,
-    files: Vec<Rc<File>>,
-    indirect_files: Vec<Rc<File>>,
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+};
+
+use tree::{DirId, FileSystem};
+
+pub mod cleanup;
+pub mod render;
+pub mod replay;
+pub mod tree;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseEventKind {
+    CdUp,
+    CdRoot,
+    Cd(String),
+    Ls,
+    Dir(String),
+    File { name: String, size: u128 },
 }
 
-#[derive(Debug, Clone)]
-pub struct File {
-    path: String,
-    size: u128,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseEvent {
+    pub line_number: usize,
+    pub kind: ParseEventKind,
+    /// The current directory after the line has been applied.
+    pub current_dir: DirId,
 }
 
-impl Directory {
-    pub fn size(&self) -> u128 {
-        self.files
-            .iter()
-            .chain(self.indirect_files.iter())
-            .map(|f| f.size)
-            .sum()
+impl ParseEvent {
+    pub fn render(&self, fs: &FileSystem) -> String {
+        let description = match &self.kind {
+            ParseEventKind::CdUp => String::from("cd up one level"),
+            ParseEventKind::CdRoot => String::from("cd to /"),
+            ParseEventKind::Cd(name) => format!("cd to {}", name),
+            ParseEventKind::Ls => String::from("list directory contents"),
+            ParseEventKind::Dir(name) => format!("found directory {}", name),
+            ParseEventKind::File { name, size } => {
+                format!("found file {} with size {}", name, size)
+            }
+        };
+
+        format!(
+            "{:>5}: {} (current path: {})",
+            self.line_number,
+            description,
+            fs.path(self.current_dir)
+        )
     }
 }
 
-pub fn parse_input(input: &str) -> Vec<Directory> {
-    let mut current_path = PathBuf::new();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Reports every anomaly in the transcript and fails if there were any.
+    Strict,
+    /// Repairs what it can: repeated listings are deduplicated, `cd ..` at the
+    /// root stays at the root and unrecognised lines are skipped.
+    Lenient,
+}
 
-    let mut fs_map: HashMap<String, Directory> = HashMap::new();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnomalyKind {
+    CdIntoUnlistedDir(String),
+    CdAboveRoot,
+    RepeatedListing(String),
+    ConflictingFileSize {
+        name: String,
+        listed_size: u128,
+        size: u128,
+    },
+    OutputWithoutLs,
+    UnexpectedLine(String),
+}
 
-    let lines = input.lines().collect::<Vec<_>>();
-    for line in lines.iter() {
-        let split_line: Vec<&str> = line.split(' ').collect();
-        println!("{:?}", split_line);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anomaly {
+    pub line_number: usize,
+    pub kind: AnomalyKind,
+}
 
-        match split_line[..] {
-            ["$", "cd", ".."] => {
-                println!("Found cd up one level command");
-                current_path.pop();
-            }
-            ["$", "cd", path] => {
-                println!("Found cd to {} command", path);
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line_number)?;
 
-                current_path.push(path);
+        match &self.kind {
+            AnomalyKind::CdIntoUnlistedDir(name) => {
+                write!(f, "cd into directory {} which was never listed", name)
+            }
+            AnomalyKind::CdAboveRoot => write!(f, "cd .. above the root directory"),
+            AnomalyKind::RepeatedListing(path) => write!(f, "{} was already listed", path),
+            AnomalyKind::ConflictingFileSize {
+                name,
+                listed_size,
+                size,
+            } => write!(
+                f,
+                "file {} was already listed with size {}, not {}",
+                name, listed_size, size
+            ),
+            AnomalyKind::OutputWithoutLs => write!(f, "listing output without a preceding ls"),
+            AnomalyKind::UnexpectedLine(line) => write!(f, "unexpected input {:?}", line),
+        }
+    }
+}
 
-                let abs_path = current_path.to_str().unwrap();
+pub fn parse_input(input: &str) -> FileSystem {
+    parse_input_with_trace(input, |_, _| {})
+}
 
-                fs_map.entry(abs_path.to_string()).or_insert(Directory {
-                    path: abs_path.to_string(),
-                    files: vec![],
-                    indirect_files: vec![],
-                });
-            }
-            ["$", "ls"] => println!("Found list directory contents command"),
-            ["dir", dir_name] => {
-                println!("Found directory {}", dir_name);
+/// Like `parse_input`, but calls `on_event` with the filesystem built so far
+/// after each line is applied.
+pub fn parse_input_with_trace(
+    input: &str,
+    on_event: impl FnMut(&FileSystem, &ParseEvent),
+) -> FileSystem {
+    parse_transcript(input, ParseMode::Lenient, on_event)
+        .expect("Lenient parsing should not fail")
+        .0
+}
 
-                current_path.push(dir_name);
+pub fn parse_input_strict(input: &str) -> Result<FileSystem, Vec<Anomaly>> {
+    parse_transcript(input, ParseMode::Strict, |_, _| {}).map(|(fs, _)| fs)
+}
 
-                let abs_path = current_path.to_str().unwrap();
+/// Builds the filesystem described by `input`. Alongside it comes every anomaly
+/// that was repaired to build it, which is always empty in strict mode.
+pub fn parse_transcript(
+    input: &str,
+    mode: ParseMode,
+    mut on_event: impl FnMut(&FileSystem, &ParseEvent),
+) -> Result<(FileSystem, Vec<Anomaly>), Vec<Anomaly>> {
+    let mut fs = FileSystem::new();
+    let mut current_dir = fs.root();
+
+    let mut listed_dirs: HashSet<DirId> = HashSet::from([fs.root()]);
+    let mut ls_dirs: HashSet<DirId> = HashSet::new();
+    let mut in_listing = false;
+    let mut anomalies = vec![];
+
+    for (idx, line) in input.lines().enumerate() {
+        let line_number = idx + 1;
+        let mut report = |kind| anomalies.push(Anomaly { line_number, kind });
 
-                fs_map.entry(abs_path.to_string()).or_insert(Directory {
-                    path: abs_path.to_string(),
-                    files: vec![],
-                    indirect_files: vec![],
-                });
+        let split_line: Vec<&str> = line.split(' ').collect();
 
-                current_path.pop();
+        let kind = match split_line[..] {
+            ["$", "cd", ".."] => {
+                in_listing = false;
+                match fs.dir(current_dir).parent {
+                    Some(parent) => current_dir = parent,
+                    None => report(AnomalyKind::CdAboveRoot),
+                }
+                ParseEventKind::CdUp
+            }
+            ["$", "cd", "/"] => {
+                in_listing = false;
+                current_dir = fs.root();
+                ParseEventKind::CdRoot
             }
-            [size, file_name] => {
-                println!("Found file {} with size {}", file_name, size);
-
-                let mut file_name_path = current_path.clone();
-                file_name_path.push(file_name);
-
-                let f = Rc::new(File {
-                    path: file_name_path.to_str().unwrap().to_string(),
-                    size: size.parse::<u128>().unwrap(),
-                });
-
-                // Add as child of immediate parent
-                fs_map
-                    .entry(current_path.to_str().unwrap().to_string())
-                    .and_modify(|directory| {
-                        directory.files.push(f.clone());
-                    })
-                    .or_insert(Directory {
-                        path: current_path.to_str().unwrap().to_string(),
-                        files: vec![f.clone()],
-                        indirect_files: vec![],
-                    });
-
-                // Add as indirect child to all ancestor directories
-                let mut traversing_path = current_path.clone();
-                while traversing_path.pop() {
-                    fs_map
-                        .entry(traversing_path.to_str().unwrap().to_string())
-                        .and_modify(|directory| {
-                            directory.indirect_files.push(f.clone());
-                        })
-                        .or_insert(Directory {
-                            path: traversing_path.to_str().unwrap().to_string(),
-                            files: vec![],
-                            indirect_files: vec![f.clone()],
-                        });
+            ["$", "cd", path] => {
+                in_listing = false;
+                current_dir = fs.add_dir(current_dir, path);
+                if !listed_dirs.contains(&current_dir) {
+                    report(AnomalyKind::CdIntoUnlistedDir(path.to_string()));
+                    listed_dirs.insert(current_dir);
                 }
+                ParseEventKind::Cd(path.to_string())
             }
-            _ => panic!("Unhandled input"),
-        }
+            ["$", "ls"] => {
+                in_listing = true;
+                if !ls_dirs.insert(current_dir) {
+                    report(AnomalyKind::RepeatedListing(fs.path(current_dir)));
+                }
+                ParseEventKind::Ls
+            }
+            ["dir", dir_name] => {
+                if !in_listing {
+                    report(AnomalyKind::OutputWithoutLs);
+                }
+                listed_dirs.insert(fs.add_dir(current_dir, dir_name));
+                ParseEventKind::Dir(dir_name.to_string())
+            }
+            [size, file_name] if size.parse::<u128>().is_ok() => {
+                if !in_listing {
+                    report(AnomalyKind::OutputWithoutLs);
+                }
+                let size = size.parse::<u128>().unwrap();
+                match fs.find_file(current_dir, file_name) {
+                    Some(file) => {
+                        let listed_size = fs.file(file).size;
+                        if listed_size != size {
+                            report(AnomalyKind::ConflictingFileSize {
+                                name: file_name.to_string(),
+                                listed_size,
+                                size,
+                            });
+                        }
+                    }
+                    None => {
+                        fs.add_file(current_dir, file_name, size);
+                    }
+                }
+                ParseEventKind::File {
+                    name: file_name.to_string(),
+                    size,
+                }
+            }
+            _ => {
+                report(AnomalyKind::UnexpectedLine(line.to_string()));
+                continue;
+            }
+        };
+
+        on_event(
+            &fs,
+            &ParseEvent {
+                line_number,
+                kind,
+                current_dir,
+            },
+        );
+    }
 
-        println!("Current path: {:#?}", &current_path);
+    if mode == ParseMode::Strict && !anomalies.is_empty() {
+        return Err(anomalies);
     }
 
-    println!("{:?}", fs_map);
+    fs.compute_sizes();
 
-    fs_map.values().cloned().collect()
+    Ok((fs, anomalies))
 }
 
-pub fn sum_dirs_sizes_with_limit(dirs: &[Directory], limit: u128) -> u128 {
-    dirs.iter()
-        .filter_map(|n| {
-            let size = n.size();
+pub fn sum_dirs_sizes_with_limit(fs: &FileSystem, limit: u128) -> u128 {
+    fs.dirs()
+        .filter_map(|(_, dir)| {
+            let size = dir.size();
             if size > limit {
                 None
             } else {
@@ -132,21 +245,20 @@ pub fn sum_dirs_sizes_with_limit(dirs: &[Directory], limit: u128) -> u128 {
 }
 
 pub fn find_smallest_dir_to_delete(
-    dirs: &[Directory],
+    fs: &FileSystem,
     max_fs_space: u128,
     required_space: u128,
-) -> &Directory {
-    let root_dir = dirs.iter().find(|dir| dir.path == "/").unwrap();
-    let total_used_space = root_dir.size();
+) -> DirId {
+    let total_used_space = fs.dir(fs.root()).size();
     let total_unused_space = max_fs_space - total_used_space;
     let total_space_to_delete = required_space - total_unused_space;
 
-    let mut possible_dirs: Vec<_> = dirs
-        .iter()
-        .filter_map(|dir| {
+    let mut possible_dirs: Vec<_> = fs
+        .dirs()
+        .filter_map(|(id, dir)| {
             let size = dir.size();
             if size >= total_space_to_delete {
-                Some((size, dir))
+                Some((size, id))
             } else {
                 None
             }
@@ -168,14 +280,167 @@ mod tests {
         let input =
             fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
 
-        let dirs = parse_input(&input);
+        let file_system = parse_input(&input);
 
-        let sum: u128 = sum_dirs_sizes_with_limit(&dirs, 100000);
+        let sum: u128 = sum_dirs_sizes_with_limit(&file_system, 100000);
 
         assert_eq!(sum, 95437);
 
-        let dir_to_delete = find_smallest_dir_to_delete(&dirs, 70000000, 30000000);
+        let dir_to_delete = find_smallest_dir_to_delete(&file_system, 70000000, 30000000);
+
+        assert_eq!(file_system.path(dir_to_delete), "/d");
+    }
+
+    #[test]
+    fn strict_accepts_example() {
+        let input =
+            fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
 
-        assert_eq!(dir_to_delete.path, "/d");
+        let file_system = parse_input_strict(&input).unwrap();
+
+        assert_eq!(sum_dirs_sizes_with_limit(&file_system, 100000), 95437);
+    }
+
+    #[test]
+    fn strict_reports_anomalies() {
+        let input = "\
+$ cd /
+$ cd ..
+$ ls
+dir a
+100 b.txt
+$ cd x
+$ cd /
+$ ls
+200 b.txt
+hello
+$ cd a
+42 c.txt";
+
+        let anomalies = parse_input_strict(input).unwrap_err();
+
+        assert_eq!(
+            anomalies,
+            vec![
+                Anomaly {
+                    line_number: 2,
+                    kind: AnomalyKind::CdAboveRoot
+                },
+                Anomaly {
+                    line_number: 6,
+                    kind: AnomalyKind::CdIntoUnlistedDir(String::from("x"))
+                },
+                Anomaly {
+                    line_number: 8,
+                    kind: AnomalyKind::RepeatedListing(String::from("/"))
+                },
+                Anomaly {
+                    line_number: 9,
+                    kind: AnomalyKind::ConflictingFileSize {
+                        name: String::from("b.txt"),
+                        listed_size: 100,
+                        size: 200
+                    }
+                },
+                Anomaly {
+                    line_number: 10,
+                    kind: AnomalyKind::UnexpectedLine(String::from("hello"))
+                },
+                Anomaly {
+                    line_number: 12,
+                    kind: AnomalyKind::OutputWithoutLs
+                },
+            ]
+        );
+        assert_eq!(
+            anomalies[1].to_string(),
+            "line 6: cd into directory x which was never listed"
+        );
+    }
+
+    #[test]
+    fn lenient_deduplicates_repeated_listings() {
+        let input = "\
+$ cd /
+$ ls
+dir a
+100 b.txt
+$ cd a
+$ ls
+5 c.txt
+$ cd ..
+$ cd ..
+$ ls
+dir a
+100 b.txt";
+
+        let file_system = parse_input(input);
+
+        assert_eq!(file_system.dir(file_system.root()).size(), 105);
+        assert_eq!(file_system.files().count(), 2);
+    }
+
+    #[test]
+    fn lenient_returns_repaired_anomalies() {
+        let input = "\
+$ cd /
+$ ls
+100 b.txt
+$ cd ..
+$ ls
+200 b.txt";
+
+        let (file_system, anomalies) =
+            parse_transcript(input, ParseMode::Lenient, |_, _| {}).unwrap();
+
+        assert_eq!(file_system.dir(file_system.root()).size(), 100);
+        assert_eq!(
+            anomalies,
+            vec![
+                Anomaly {
+                    line_number: 4,
+                    kind: AnomalyKind::CdAboveRoot
+                },
+                Anomaly {
+                    line_number: 5,
+                    kind: AnomalyKind::RepeatedListing(String::from("/"))
+                },
+                Anomaly {
+                    line_number: 6,
+                    kind: AnomalyKind::ConflictingFileSize {
+                        name: String::from("b.txt"),
+                        listed_size: 100,
+                        size: 200
+                    }
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn trace() {
+        let input = "\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+$ cd a";
+
+        let mut events = vec![];
+        let file_system = parse_input_with_trace(input, |fs, event| {
+            events.push(event.render(fs));
+        });
+
+        assert_eq!(
+            events,
+            vec![
+                "    1: cd to / (current path: /)",
+                "    2: list directory contents (current path: /)",
+                "    3: found directory a (current path: /)",
+                "    4: found file b.txt with size 14848514 (current path: /)",
+                "    5: cd to a (current path: /a)",
+            ]
+        );
+        assert_eq!(file_system.dir(file_system.root()).size(), 14848514);
     }
 }
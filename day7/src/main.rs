@@ -1,21 +1,84 @@
-use std::fs;
+use std::{collections::HashSet, env, fs, process};
 
-use day7::{find_smallest_dir_to_delete, parse_input, sum_dirs_sizes_with_limit};
+use day7::{
+    cleanup::plan_cleanup,
+    find_smallest_dir_to_delete, parse_input_strict, parse_transcript,
+    render::{render_du, render_json, render_tree, RenderOptions, SortBy},
+    sum_dirs_sizes_with_limit, ParseMode,
+};
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let verbose = args.iter().any(|arg| arg == "--verbose");
+
+    let mut options = RenderOptions::default();
+    for arg in args.iter() {
+        match arg.split_once('=') {
+            Some(("--sort", "name")) => options.sort_by = SortBy::Name,
+            Some(("--sort", "size")) => options.sort_by = SortBy::Size,
+            Some(("--depth", depth)) => {
+                options.max_depth = Some(depth.parse().expect("Depth should be a number"))
+            }
+            _ => (),
+        }
+    }
+
     let input = fs::read_to_string("input.txt").expect("Should have been able to read the file");
 
-    let dirs = parse_input(&input);
+    let file_system = if args.iter().any(|arg| arg == "--strict") {
+        parse_input_strict(&input).unwrap_or_else(|anomalies| {
+            for anomaly in anomalies.iter() {
+                eprintln!("{}", anomaly);
+            }
+            process::exit(1);
+        })
+    } else {
+        let (file_system, anomalies) = parse_transcript(&input, ParseMode::Lenient, |fs, event| {
+            if verbose {
+                println!("{}", event.render(fs));
+            }
+        })
+        .expect("Lenient parsing should not fail");
+        for anomaly in anomalies.iter() {
+            eprintln!("repaired {}", anomaly);
+        }
+        file_system
+    };
+
+    if args.iter().any(|arg| arg == "--tree") {
+        print!("{}", render_tree(&file_system, &options));
+    }
+
+    if args.iter().any(|arg| arg == "--du") {
+        print!("{}", render_du(&file_system, &options));
+    }
+
+    if args.iter().any(|arg| arg == "--json") {
+        println!("{}", render_json(&file_system, &options));
+    }
 
-    let sum: u128 = sum_dirs_sizes_with_limit(&dirs, 100000);
+    let sum: u128 = sum_dirs_sizes_with_limit(&file_system, 100000);
 
     println!("Total size of directories: {}", sum);
 
-    let dir_to_delete = find_smallest_dir_to_delete(&dirs, 70000000, 30000000);
+    let dir_to_delete = find_smallest_dir_to_delete(&file_system, 70000000, 30000000);
 
     println!(
         "Smallest dir to delete: {}, size: {}",
-        dir_to_delete.path,
-        dir_to_delete.size()
+        file_system.path(dir_to_delete),
+        file_system.dir(dir_to_delete).size()
+    );
+
+    let plan = plan_cleanup(&file_system, 70000000, 30000000, &HashSet::new())
+        .expect("Should be able to free enough space");
+
+    println!(
+        "Smallest set of dirs to delete: {}, size: {}",
+        plan.dirs
+            .iter()
+            .map(|dir| file_system.path(*dir))
+            .collect::<Vec<_>>()
+            .join(", "),
+        plan.space_freed
     );
 }
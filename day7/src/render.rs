@@ -0,0 +1,329 @@
+use std::fmt::Write;
+
+use crate::tree::{DirId, FileSystem, NodeId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    Name,
+    /// Largest first, ties broken by name.
+    Size,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub sort_by: SortBy,
+    /// How many levels below the root to render. `None` renders everything.
+    pub max_depth: Option<usize>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            sort_by: SortBy::Name,
+            max_depth: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    fn includes_depth(&self, depth: usize) -> bool {
+        self.max_depth.is_none_or(|max_depth| depth <= max_depth)
+    }
+
+    fn sorted_children(&self, fs: &FileSystem, id: DirId) -> Vec<NodeId> {
+        let mut children: Vec<_> = fs.children(id).collect();
+
+        match self.sort_by {
+            SortBy::Name => children.sort_by(|a, b| fs.name(*a).cmp(fs.name(*b))),
+            SortBy::Size => children.sort_by(|a, b| {
+                fs.size(*b)
+                    .cmp(&fs.size(*a))
+                    .then_with(|| fs.name(*a).cmp(fs.name(*b)))
+            }),
+        }
+
+        children
+    }
+}
+
+/// Formats `size` in powers of 1024 like `du -h`, rounding up, e.g. `584B`, `1.5K`, `47M`.
+pub fn human_readable(size: u128) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+
+    if size < 1024 {
+        return format!("{}B", size);
+    }
+
+    // Like du, round up rather than to nearest, so a size is never understated.
+    let mut divisor = 1;
+    for (unit, name) in UNITS.iter().enumerate() {
+        divisor *= 1024;
+
+        let tenths = size / divisor * 10 + (size % divisor * 10).div_ceil(divisor);
+        if tenths < 100 {
+            return format!("{}.{}{}", tenths / 10, tenths % 10, name);
+        }
+
+        let whole = size.div_ceil(divisor);
+        if whole < 1024 || unit == UNITS.len() - 1 {
+            return format!("{}{}", whole, name);
+        }
+    }
+
+    unreachable!()
+}
+
+/// Renders the filesystem as an indented hierarchy in the style of `tree`.
+pub fn render_tree(fs: &FileSystem, options: &RenderOptions) -> String {
+    let root = fs.root();
+    let mut output = format!("/ ({})\n", human_readable(fs.dir(root).size()));
+
+    render_tree_children(fs, options, root, "", 1, &mut output);
+
+    output
+}
+
+fn render_tree_children(
+    fs: &FileSystem,
+    options: &RenderOptions,
+    id: DirId,
+    prefix: &str,
+    depth: usize,
+    output: &mut String,
+) {
+    if !options.includes_depth(depth) {
+        return;
+    }
+
+    let children = options.sorted_children(fs, id);
+
+    for (idx, child) in children.iter().enumerate() {
+        let is_last = idx == children.len() - 1;
+        let (branch, indent) = if is_last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+
+        writeln!(
+            output,
+            "{}{}{} ({})",
+            prefix,
+            branch,
+            fs.name(*child),
+            human_readable(fs.size(*child))
+        )
+        .unwrap();
+
+        if let NodeId::Dir(child_id) = child {
+            let child_prefix = format!("{}{}", prefix, indent);
+            render_tree_children(fs, options, *child_id, &child_prefix, depth + 1, output);
+        }
+    }
+}
+
+/// Renders one line per directory in the style of `du -h`, with each directory
+/// listed after its subdirectories.
+pub fn render_du(fs: &FileSystem, options: &RenderOptions) -> String {
+    let mut output = String::new();
+
+    render_du_dir(fs, options, fs.root(), 0, &mut output);
+
+    output
+}
+
+fn render_du_dir(
+    fs: &FileSystem,
+    options: &RenderOptions,
+    id: DirId,
+    depth: usize,
+    output: &mut String,
+) {
+    for child in options.sorted_children(fs, id) {
+        if let NodeId::Dir(child_id) = child {
+            render_du_dir(fs, options, child_id, depth + 1, output);
+        }
+    }
+
+    if options.includes_depth(depth) {
+        writeln!(
+            output,
+            "{}\t{}",
+            human_readable(fs.dir(id).size()),
+            fs.path(id)
+        )
+        .unwrap();
+    }
+}
+
+/// Exports the filesystem as a JSON object of nested `dir` and `file` nodes with
+/// their exact sizes in bytes.
+pub fn render_json(fs: &FileSystem, options: &RenderOptions) -> String {
+    let mut output = String::new();
+
+    render_json_node(fs, options, NodeId::Dir(fs.root()), 0, &mut output);
+
+    output
+}
+
+fn render_json_node(
+    fs: &FileSystem,
+    options: &RenderOptions,
+    node: NodeId,
+    depth: usize,
+    output: &mut String,
+) {
+    let kind = match node {
+        NodeId::Dir(_) => "dir",
+        NodeId::File(_) => "file",
+    };
+
+    write!(
+        output,
+        "{{\"name\":{},\"type\":\"{}\",\"size\":{}",
+        json_string(fs.name(node)),
+        kind,
+        fs.size(node)
+    )
+    .unwrap();
+
+    if let NodeId::Dir(id) = node {
+        output.push_str(",\"children\":[");
+
+        if options.includes_depth(depth + 1) {
+            for (idx, child) in options.sorted_children(fs, id).into_iter().enumerate() {
+                if idx > 0 {
+                    output.push(',');
+                }
+                render_json_node(fs, options, child, depth + 1, output);
+            }
+        }
+
+        output.push(']');
+    }
+
+    output.push('}');
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::from("\"");
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::parse_input;
+
+    fn example() -> FileSystem {
+        let input =
+            fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
+
+        parse_input(&input)
+    }
+
+    #[test]
+    fn human_readable_units() {
+        assert_eq!(human_readable(584), "584B");
+        assert_eq!(human_readable(1536), "1.5K");
+        assert_eq!(human_readable(94853), "93K");
+        assert_eq!(human_readable(48381165), "47M");
+        assert_eq!(human_readable(14848514), "15M");
+        assert_eq!(human_readable(1048524), "1.0M");
+    }
+
+    #[test]
+    fn tree_by_name() {
+        let expected = "\
+/ (47M)
+├── a (93K)
+│   ├── e (584B)
+│   │   └── i (584B)
+│   ├── f (29K)
+│   ├── g (2.5K)
+│   └── h.lst (62K)
+├── b.txt (15M)
+├── c.dat (8.2M)
+└── d (24M)
+    ├── d.ext (5.4M)
+    ├── d.log (7.7M)
+    ├── j (3.9M)
+    └── k (6.9M)
+";
+
+        assert_eq!(render_tree(&example(), &RenderOptions::default()), expected);
+    }
+
+    #[test]
+    fn tree_by_size_with_depth_limit() {
+        let options = RenderOptions {
+            sort_by: SortBy::Size,
+            max_depth: Some(1),
+        };
+
+        let expected = "\
+/ (47M)
+├── d (24M)
+├── b.txt (15M)
+├── c.dat (8.2M)
+└── a (93K)
+";
+
+        assert_eq!(render_tree(&example(), &options), expected);
+    }
+
+    #[test]
+    fn du() {
+        let expected = "\
+584B\t/a/e
+93K\t/a
+24M\t/d
+47M\t/
+";
+
+        assert_eq!(render_du(&example(), &RenderOptions::default()), expected);
+
+        let options = RenderOptions {
+            sort_by: SortBy::Name,
+            max_depth: Some(0),
+        };
+
+        assert_eq!(render_du(&example(), &options), "47M\t/\n");
+    }
+
+    #[test]
+    fn json() {
+        let mut fs = FileSystem::new();
+        let root = fs.root();
+        let a = fs.add_dir(root, "a");
+        fs.add_file(a, "f\"1", 10);
+        fs.add_file(root, "b", 5);
+        fs.compute_sizes();
+
+        assert_eq!(
+            render_json(&fs, &RenderOptions::default()),
+            concat!(
+                r#"{"name":"/","type":"dir","size":15,"children":["#,
+                r#"{"name":"a","type":"dir","size":10,"children":["#,
+                r#"{"name":"f\"1","type":"file","size":10}]},"#,
+                r#"{"name":"b","type":"file","size":5}]}"#,
+            )
+        );
+    }
+}
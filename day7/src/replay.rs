@@ -0,0 +1,219 @@
+use std::{
+    fmt::Write,
+    fs::{self, File},
+    io::{self, ErrorKind},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::tree::{DirId, FileSystem};
+
+/// Recreates `fs` beneath `root`, writing each file as a sparse file of its size.
+///
+/// Fails with `InvalidInput` if any name isn't a single plain path component,
+/// so nothing can be written outside `root`.
+pub fn materialise(fs: &FileSystem, root: &Path) -> io::Result<()> {
+    materialise_dir(fs, fs.root(), root)
+}
+
+fn materialise_dir(fs: &FileSystem, id: DirId, path: &Path) -> io::Result<()> {
+    fs::create_dir_all(path)?;
+
+    for file_id in fs.dir(id).files() {
+        let file = fs.file(file_id);
+        let size = u64::try_from(file.size).map_err(|_| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} is too large for a file", file.size),
+            )
+        })?;
+        File::create(join_name(path, &file.name)?)?.set_len(size)?;
+    }
+
+    for child in fs.dir(id).dirs() {
+        materialise_dir(fs, child, &join_name(path, &fs.dir(child).name)?)?;
+    }
+
+    Ok(())
+}
+
+fn join_name(path: &Path, name: &str) -> io::Result<PathBuf> {
+    let mut components = Path::new(name).components();
+
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(component)), None) if component == name => Ok(path.join(name)),
+        _ => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("{:?} is not a plain file name", name),
+        )),
+    }
+}
+
+/// Walks the real directory at `root` and writes the `$ cd` / `$ ls` session that
+/// would explore it, visiting entries in name order. Symlinks are skipped.
+pub fn transcript_from_dir(root: &Path) -> io::Result<String> {
+    let mut transcript = String::from("$ cd /\n");
+
+    transcript_dir(root, &mut transcript)?;
+
+    Ok(transcript)
+}
+
+fn transcript_dir(path: &Path, transcript: &mut String) -> io::Result<()> {
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| {
+            let entry = entry?;
+            let name = entry.file_name().into_string().map_err(|name| {
+                io::Error::new(ErrorKind::InvalidData, format!("{:?} is not UTF-8", name))
+            })?;
+            if name.contains(char::is_whitespace) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{:?} can't be written to a transcript", name),
+                ));
+            }
+            Ok((name, entry.metadata()?))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    transcript.push_str("$ ls\n");
+
+    for (name, metadata) in entries.iter() {
+        if metadata.is_dir() {
+            writeln!(transcript, "dir {}", name).unwrap();
+        } else if metadata.is_file() {
+            writeln!(transcript, "{} {}", metadata.len(), name).unwrap();
+        }
+    }
+
+    for (name, metadata) in entries.iter() {
+        if metadata.is_dir() {
+            writeln!(transcript, "$ cd {}", name).unwrap();
+            transcript_dir(&path.join(name), transcript)?;
+            transcript.push_str("$ cd ..\n");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf, process};
+
+    use super::*;
+    use crate::{
+        parse_input, parse_input_strict,
+        render::{render_json, RenderOptions},
+    };
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("day7-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&path);
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn materialise_example() {
+        let input =
+            fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
+        let file_system = parse_input(&input);
+        let temp_dir = TempDir::new("materialise");
+
+        materialise(&file_system, &temp_dir.0).unwrap();
+
+        assert_eq!(
+            fs::metadata(temp_dir.0.join("b.txt")).unwrap().len(),
+            14848514
+        );
+        assert_eq!(fs::metadata(temp_dir.0.join("a/e/i")).unwrap().len(), 584);
+        assert!(temp_dir.0.join("d").is_dir());
+    }
+
+    #[test]
+    fn materialise_rejects_escaping_names() {
+        let temp_dir = TempDir::new("escape");
+        let outside = temp_dir
+            .0
+            .with_file_name(format!("day7-escape-outside-{}", process::id()));
+
+        for transcript in [
+            format!("$ cd /\n$ ls\n123 {}\n", outside.display()),
+            String::from("$ cd /\n$ ls\ndir ..\n"),
+            String::from("$ cd /\n$ ls\n5 a/b\n"),
+            String::from("$ cd /\n$ ls\ndir .\n"),
+        ] {
+            let file_system = parse_input(&transcript);
+
+            let error = materialise(&file_system, &temp_dir.0).unwrap_err();
+
+            assert_eq!(error.kind(), ErrorKind::InvalidInput, "{}", transcript);
+        }
+        assert!(!outside.exists());
+        assert!(!temp_dir.0.join("a").exists());
+    }
+
+    #[test]
+    fn materialise_rejects_oversized_files() {
+        let temp_dir = TempDir::new("oversized");
+        let file_system = parse_input(&format!("$ cd /\n$ ls\n{} big\n", 1u128 << 64));
+
+        let error = materialise(&file_system, &temp_dir.0).unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(!temp_dir.0.join("big").exists());
+    }
+
+    #[test]
+    fn transcript_from_real_dir() {
+        let temp_dir = TempDir::new("transcript");
+        fs::create_dir_all(temp_dir.0.join("b/c")).unwrap();
+        fs::write(temp_dir.0.join("a.txt"), "hello").unwrap();
+        fs::write(temp_dir.0.join("b/c/d"), "!").unwrap();
+
+        let expected = "\
+$ cd /
+$ ls
+5 a.txt
+dir b
+$ cd b
+$ ls
+dir c
+$ cd c
+$ ls
+1 d
+$ cd ..
+$ cd ..
+";
+
+        assert_eq!(transcript_from_dir(&temp_dir.0).unwrap(), expected);
+    }
+
+    #[test]
+    fn round_trip() {
+        let input =
+            fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
+        let file_system = parse_input(&input);
+        let temp_dir = TempDir::new("round-trip");
+
+        materialise(&file_system, &temp_dir.0).unwrap();
+        let transcript = transcript_from_dir(&temp_dir.0).unwrap();
+        let replayed = parse_input_strict(&transcript).unwrap();
+
+        let options = RenderOptions::default();
+        assert_eq!(
+            render_json(&replayed, &options),
+            render_json(&file_system, &options)
+        );
+    }
+}
@@ -0,0 +1,251 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DirId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeId {
+    Dir(DirId),
+    File(FileId),
+}
+
+#[derive(Debug, Clone)]
+pub struct DirNode {
+    pub name: String,
+    pub parent: Option<DirId>,
+    dirs: Vec<DirId>,
+    files: Vec<FileId>,
+    size: u128,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileNode {
+    pub name: String,
+    pub parent: DirId,
+    pub size: u128,
+}
+
+impl DirNode {
+    fn new(name: &str, parent: Option<DirId>) -> Self {
+        DirNode {
+            name: name.to_string(),
+            parent,
+            dirs: vec![],
+            files: vec![],
+            size: 0,
+        }
+    }
+
+    /// The cumulative size of every file beneath this directory, as of the last
+    /// call to `FileSystem::compute_sizes`.
+    pub fn size(&self) -> u128 {
+        self.size
+    }
+
+    pub fn dirs(&self) -> impl Iterator<Item = DirId> + '_ {
+        self.dirs.iter().copied()
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = FileId> + '_ {
+        self.files.iter().copied()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileSystem {
+    dirs: Vec<DirNode>,
+    files: Vec<FileNode>,
+}
+
+impl FileSystem {
+    pub fn new() -> Self {
+        FileSystem {
+            dirs: vec![DirNode::new("/", None)],
+            files: vec![],
+        }
+    }
+
+    pub fn root(&self) -> DirId {
+        DirId(0)
+    }
+
+    pub fn dir(&self, id: DirId) -> &DirNode {
+        &self.dirs[id.0]
+    }
+
+    pub fn file(&self, id: FileId) -> &FileNode {
+        &self.files[id.0]
+    }
+
+    pub fn dirs(&self) -> impl Iterator<Item = (DirId, &DirNode)> {
+        self.dirs
+            .iter()
+            .enumerate()
+            .map(|(idx, dir)| (DirId(idx), dir))
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = (FileId, &FileNode)> {
+        self.files
+            .iter()
+            .enumerate()
+            .map(|(idx, file)| (FileId(idx), file))
+    }
+
+    /// Iterates over the subdirectories of `id` followed by its files.
+    pub fn children(&self, id: DirId) -> impl Iterator<Item = NodeId> + '_ {
+        let dir = self.dir(id);
+        dir.dirs()
+            .map(NodeId::Dir)
+            .chain(dir.files().map(NodeId::File))
+    }
+
+    pub fn name(&self, node: NodeId) -> &str {
+        match node {
+            NodeId::Dir(id) => &self.dir(id).name,
+            NodeId::File(id) => &self.file(id).name,
+        }
+    }
+
+    pub fn size(&self, node: NodeId) -> u128 {
+        match node {
+            NodeId::Dir(id) => self.dir(id).size(),
+            NodeId::File(id) => self.file(id).size,
+        }
+    }
+
+    pub fn find_dir(&self, parent: DirId, name: &str) -> Option<DirId> {
+        self.dir(parent)
+            .dirs()
+            .find(|id| self.dir(*id).name == name)
+    }
+
+    pub fn find_file(&self, parent: DirId, name: &str) -> Option<FileId> {
+        self.dir(parent)
+            .files()
+            .find(|id| self.file(*id).name == name)
+    }
+
+    /// Returns the existing child directory called `name`, or creates it.
+    pub fn add_dir(&mut self, parent: DirId, name: &str) -> DirId {
+        if let Some(id) = self.find_dir(parent, name) {
+            return id;
+        }
+
+        let id = DirId(self.dirs.len());
+        self.dirs.push(DirNode::new(name, Some(parent)));
+        self.dirs[parent.0].dirs.push(id);
+        id
+    }
+
+    pub fn add_file(&mut self, parent: DirId, name: &str, size: u128) -> FileId {
+        let id = FileId(self.files.len());
+        self.files.push(FileNode {
+            name: name.to_string(),
+            parent,
+            size,
+        });
+        self.dirs[parent.0].files.push(id);
+        id
+    }
+
+    pub fn ancestors(&self, id: DirId) -> impl Iterator<Item = DirId> + '_ {
+        std::iter::successors(self.dir(id).parent, |id| self.dir(*id).parent)
+    }
+
+    pub fn path(&self, id: DirId) -> String {
+        let mut names: Vec<_> = std::iter::once(id)
+            .chain(self.ancestors(id))
+            .map(|id| self.dir(id).name.as_str())
+            .collect();
+        names.pop();
+        names.reverse();
+
+        format!("/{}", names.join("/"))
+    }
+
+    /// Recomputes every directory's cumulative size in a single post-order pass.
+    pub fn compute_sizes(&mut self) {
+        let mut stack = vec![(self.root(), false)];
+
+        while let Some((id, children_done)) = stack.pop() {
+            if children_done {
+                let dir = &self.dirs[id.0];
+                let size = dir.files().map(|f| self.file(f).size).sum::<u128>()
+                    + dir.dirs().map(|d| self.dir(d).size).sum::<u128>();
+                self.dirs[id.0].size = size;
+            } else {
+                stack.push((id, true));
+                stack.extend(self.dir(id).dirs().map(|child| (child, false)));
+            }
+        }
+    }
+}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> FileSystem {
+        let mut fs = FileSystem::new();
+        let root = fs.root();
+        let a = fs.add_dir(root, "a");
+        let e = fs.add_dir(a, "e");
+        fs.add_file(e, "i", 584);
+        fs.add_file(a, "f", 29116);
+        fs.add_file(root, "b.txt", 14848514);
+        fs.compute_sizes();
+        fs
+    }
+
+    #[test]
+    fn parent_links() {
+        let fs = example();
+        let a = fs.find_dir(fs.root(), "a").unwrap();
+        let e = fs.find_dir(a, "e").unwrap();
+
+        assert_eq!(fs.dir(e).parent, Some(a));
+        assert_eq!(fs.ancestors(e).collect::<Vec<_>>(), vec![a, fs.root()]);
+        assert_eq!(fs.path(e), "/a/e");
+        assert_eq!(fs.path(fs.root()), "/");
+    }
+
+    #[test]
+    fn child_iteration() {
+        let fs = example();
+        let a = fs.find_dir(fs.root(), "a").unwrap();
+
+        let dir_names: Vec<_> = fs.dir(a).dirs().map(|d| &fs.dir(d).name).collect();
+        let file_names: Vec<_> = fs.dir(a).files().map(|f| &fs.file(f).name).collect();
+
+        assert_eq!(dir_names, vec!["e"]);
+        assert_eq!(file_names, vec!["f"]);
+    }
+
+    #[test]
+    fn add_dir_reuses_existing() {
+        let mut fs = example();
+        let root = fs.root();
+        let a = fs.find_dir(root, "a").unwrap();
+
+        assert_eq!(fs.add_dir(root, "a"), a);
+        assert_eq!(fs.dirs().count(), 3);
+    }
+
+    #[test]
+    fn sizes() {
+        let fs = example();
+        let a = fs.find_dir(fs.root(), "a").unwrap();
+        let e = fs.find_dir(a, "e").unwrap();
+
+        assert_eq!(fs.dir(e).size(), 584);
+        assert_eq!(fs.dir(a).size(), 29700);
+        assert_eq!(fs.dir(fs.root()).size(), 14878214);
+    }
+}
@@ -1,113 +1,396 @@
-use std::{marker::PhantomData, slice::Iter, str::FromStr};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    iter::StepBy,
+    ops::{Index, IndexMut},
+    slice::Iter,
+    str::FromStr,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseGridError<E> {
+    /// The cell at `row`, `col` (both 0-based) couldn't be converted.
+    Cell { row: usize, col: usize, error: E },
+    /// Row `row` has a different number of cells to the rows before it.
+    RaggedRow {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl<E: Display> Display for ParseGridError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseGridError::Cell { row, col, error } => {
+                write!(f, "invalid cell at row {}, column {}: {}", row, col, error)
+            }
+            ParseGridError::RaggedRow {
+                row,
+                expected,
+                found,
+            } => write!(f, "row {} has {} cells, expected {}", row, found, expected),
+        }
+    }
+}
+
+impl<E: Error> Error for ParseGridError<E> {}
 
-pub struct Grid<'a, T> {
-    pub items: Vec<Vec<T>>,
-    _marker: PhantomData<&'a T>,
+/// A rectangular grid stored as a flat, row-major buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    pub items: Vec<T>,
+    num_rows: usize,
+    num_columns: usize,
 }
 
-impl<'a, T> Grid<'a, T> {
-    pub fn new(items: Vec<Vec<T>>) -> Grid<'a, T> {
+impl<T> Grid<T> {
+    pub fn new(rows: Vec<Vec<T>>) -> Grid<T> {
+        let num_rows = rows.len();
+        let num_columns = rows.first().map_or(0, |row| row.len());
+
+        assert!(
+            rows.iter().all(|row| row.len() == num_columns),
+            "All rows should have the same length"
+        );
+
+        Grid {
+            items: rows.into_iter().flatten().collect(),
+            num_rows,
+            num_columns,
+        }
+    }
+
+    pub fn from_vec(items: Vec<T>, num_columns: usize) -> Grid<T> {
+        let num_rows = items.len().checked_div(num_columns).unwrap_or(0);
+
+        assert_eq!(
+            num_rows * num_columns,
+            items.len(),
+            "The number of items should be a multiple of the number of columns"
+        );
+
         Grid {
             items,
-            _marker: PhantomData,
+            num_rows,
+            num_columns,
         }
     }
 
+    /// Parses one cell per char, with each line of `input` as a row.
+    pub fn parse_with<E>(
+        input: &str,
+        parse_cell: impl FnMut(char) -> Result<T, E>,
+    ) -> Result<Grid<T>, ParseGridError<E>> {
+        Grid::parse_rows(input.lines().map(|line| line.chars()), parse_cell)
+    }
+
+    /// Parses one cell per whitespace-separated word, with each line of `input` as
+    /// a row.
+    pub fn parse_words_with<'s, E>(
+        input: &'s str,
+        parse_cell: impl FnMut(&'s str) -> Result<T, E>,
+    ) -> Result<Grid<T>, ParseGridError<E>> {
+        Grid::parse_rows(
+            input.lines().map(|line| line.split_whitespace()),
+            parse_cell,
+        )
+    }
+
+    fn parse_rows<C, E>(
+        rows: impl Iterator<Item = impl Iterator<Item = C>>,
+        mut parse_cell: impl FnMut(C) -> Result<T, E>,
+    ) -> Result<Grid<T>, ParseGridError<E>> {
+        let mut items = vec![];
+        let mut num_columns = None;
+        let mut num_rows = 0;
+
+        for (row, cells) in rows.enumerate() {
+            let row_start = items.len();
+
+            for (col, cell) in cells.enumerate() {
+                let item =
+                    parse_cell(cell).map_err(|error| ParseGridError::Cell { row, col, error })?;
+                items.push(item);
+            }
+
+            let found = items.len() - row_start;
+            match num_columns {
+                Some(expected) if expected != found => {
+                    return Err(ParseGridError::RaggedRow {
+                        row,
+                        expected,
+                        found,
+                    })
+                }
+                _ => num_columns = Some(found),
+            }
+            num_rows += 1;
+        }
+
+        Ok(Grid {
+            items,
+            num_rows,
+            num_columns: num_columns.unwrap_or(0),
+        })
+    }
+
+    /// Renders one char per cell, with a line per row. The inverse of `parse_with`.
+    pub fn render_with(&self, mut render_cell: impl FnMut(&T) -> char) -> String {
+        let mut output = String::with_capacity(self.len() + self.num_rows());
+
+        for row_idx in 0..self.num_rows() {
+            if row_idx > 0 {
+                output.push('\n');
+            }
+            output.extend(self.row_iter(row_idx).map(&mut render_cell));
+        }
+
+        output
+    }
+
     pub fn num_rows(&self) -> usize {
-        self.items.len()
+        self.num_rows
     }
 
     pub fn num_columns(&self) -> usize {
-        self.items[0].len()
+        self.num_columns
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
     }
 
     pub fn to_row_col(&self, idx: usize) -> (usize, usize) {
-        let row = idx / self.num_rows();
+        let row = idx / self.num_columns();
         let col = idx % self.num_columns();
 
         (row, col)
     }
 
-    pub fn row_iter(&self, row_idx: usize) -> Iter<T> {
-        self.items[row_idx].iter()
+    pub fn to_idx(&self, row_idx: usize, col_idx: usize) -> usize {
+        row_idx * self.num_columns() + col_idx
     }
 
-    pub fn iter(&self) -> GridIterator<'_, T> {
-        GridIterator {
-            grid: self,
-            curr_idx: 0,
+    pub fn get(&self, row_idx: usize, col_idx: usize) -> Option<&T> {
+        if row_idx < self.num_rows() && col_idx < self.num_columns() {
+            Some(&self.items[self.to_idx(row_idx, col_idx)])
+        } else {
+            None
         }
     }
 
-    pub fn col_iter(&self, col_idx: usize) -> GridColumnIterator<'_, T> {
-        GridColumnIterator {
+    pub fn row(&self, row_idx: usize) -> &[T] {
+        let start = self.to_idx(row_idx, 0);
+        &self.items[start..start + self.num_columns()]
+    }
+
+    pub fn row_iter(&self, row_idx: usize) -> Iter<'_, T> {
+        self.row(row_idx).iter()
+    }
+
+    pub fn col_iter(&self, col_idx: usize) -> StepBy<Iter<'_, T>> {
+        assert!(col_idx < self.num_columns(), "Column index out of bounds");
+
+        self.items[col_idx..].iter().step_by(self.num_columns())
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Iterates over the positions directly above, left, right and below
+    /// `(row, col)` that are inside the grid.
+    pub fn neighbours(
+        &self,
+        (row_idx, col_idx): (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        [(-1, 0), (0, -1), (0, 1), (1, 0)]
+            .into_iter()
+            .filter_map(move |offset| self.offset((row_idx, col_idx), offset))
+    }
+
+    /// Like `neighbours`, but also includes the diagonals.
+    pub fn all_neighbours(
+        &self,
+        (row_idx, col_idx): (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        [
+            (-1, -1),
+            (-1, 0),
+            (-1, 1),
+            (0, -1),
+            (0, 1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+        ]
+        .into_iter()
+        .filter_map(move |offset| self.offset((row_idx, col_idx), offset))
+    }
+
+    fn offset(
+        &self,
+        (row_idx, col_idx): (usize, usize),
+        (d_row, d_col): (isize, isize),
+    ) -> Option<(usize, usize)> {
+        let row_idx = row_idx.checked_add_signed(d_row)?;
+        let col_idx = col_idx.checked_add_signed(d_col)?;
+
+        self.get(row_idx, col_idx).map(|_| (row_idx, col_idx))
+    }
+
+    /// Borrows the `num_rows` by `num_columns` rectangle whose top left corner is
+    /// at `(row, col)`.
+    pub fn view(
+        &self,
+        top_left: (usize, usize),
+        num_rows: usize,
+        num_columns: usize,
+    ) -> GridView<'_, T> {
+        assert!(
+            top_left.0 + num_rows <= self.num_rows()
+                && top_left.1 + num_columns <= self.num_columns(),
+            "The view should fit inside the grid"
+        );
+
+        GridView {
             grid: self,
-            col_idx,
-            curr_row_idx: 0,
+            top_left,
+            num_rows,
+            num_columns,
         }
     }
+
+    /// Iterates over every item in row-major order along with its `(row, col)`.
+    pub fn enumerate_positions(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| (self.to_row_col(idx), item))
+    }
 }
 
-impl<'a, T> FromStr for Grid<'a, T>
-where
-    T: FromStr,
-{
-    type Err = <T as FromStr>::Err;
+impl<T: Clone> Grid<T> {
+    pub fn transpose(&self) -> Grid<T> {
+        let items = (0..self.num_columns())
+            .flat_map(|col_idx| self.col_iter(col_idx).cloned())
+            .collect();
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut items: Vec<Vec<T>> = Vec::new();
+        Grid::from_vec(items, self.num_rows())
+    }
 
-        for line in s.lines() {
-            let row = line
-                .chars()
-                .map(|c| c.to_string().parse::<T>())
-                .collect::<Result<Vec<T>, _>>()?;
+    pub fn rotate_clockwise(&self) -> Grid<T> {
+        let items = (0..self.num_columns())
+            .flat_map(|col_idx| self.col_iter(col_idx).rev().cloned())
+            .collect();
 
-            items.push(row);
-        }
+        Grid::from_vec(items, self.num_rows())
+    }
+
+    pub fn rotate_counter_clockwise(&self) -> Grid<T> {
+        let items = (0..self.num_columns())
+            .rev()
+            .flat_map(|col_idx| self.col_iter(col_idx).cloned())
+            .collect();
 
-        Ok(Grid::new(items))
+        Grid::from_vec(items, self.num_rows())
     }
 }
 
-pub struct GridIterator<'a, T> {
-    grid: &'a Grid<'a, T>,
-    curr_idx: usize,
+/// A borrowed rectangular region of a `Grid`, indexed relative to its own top
+/// left corner.
+#[derive(Debug, Clone, Copy)]
+pub struct GridView<'a, T> {
+    grid: &'a Grid<T>,
+    top_left: (usize, usize),
+    num_rows: usize,
+    num_columns: usize,
 }
 
-impl<'a, T: 'a> Iterator for GridIterator<'a, T> {
-    type Item = &'a T;
+impl<'a, T> GridView<'a, T> {
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let joined_items: Vec<&T> = self.grid.items.iter().flatten().collect();
+    pub fn num_columns(&self) -> usize {
+        self.num_columns
+    }
 
-        if self.curr_idx < joined_items.len() {
-            let result = Some(joined_items[self.curr_idx]);
-            self.curr_idx += 1;
-            result
+    pub fn get(&self, row_idx: usize, col_idx: usize) -> Option<&'a T> {
+        if row_idx < self.num_rows && col_idx < self.num_columns {
+            self.grid
+                .get(self.top_left.0 + row_idx, self.top_left.1 + col_idx)
         } else {
             None
         }
     }
+
+    pub fn row(&self, row_idx: usize) -> &'a [T] {
+        assert!(row_idx < self.num_rows, "Row index out of bounds");
+
+        let start = self.top_left.1;
+        &self.grid.row(self.top_left.0 + row_idx)[start..start + self.num_columns]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a T> + '_ {
+        (0..self.num_rows).flat_map(move |row_idx| self.row(row_idx).iter())
+    }
+
+    pub fn to_grid(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid::from_vec(self.iter().cloned().collect(), self.num_columns)
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (row_idx, col_idx): (usize, usize)) -> &Self::Output {
+        assert!(col_idx < self.num_columns(), "Column index out of bounds");
+        &self.items[self.to_idx(row_idx, col_idx)]
+    }
 }
 
-pub struct GridColumnIterator<'a, T> {
-    grid: &'a Grid<'a, T>,
-    col_idx: usize,
-    curr_row_idx: usize,
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (row_idx, col_idx): (usize, usize)) -> &mut Self::Output {
+        assert!(col_idx < self.num_columns(), "Column index out of bounds");
+        let idx = self.to_idx(row_idx, col_idx);
+        &mut self.items[idx]
+    }
 }
 
-impl<'a, T> Iterator for GridColumnIterator<'a, T> {
-    type Item = &'a T;
+impl<T> FromStr for Grid<T>
+where
+    T: FromStr,
+{
+    type Err = ParseGridError<<T as FromStr>::Err>;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.curr_row_idx < self.grid.num_rows() {
-            let result = Some(&self.grid.items[self.curr_row_idx][self.col_idx]);
-            self.curr_row_idx += 1;
-            result
-        } else {
-            None
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut buf = [0; 4];
+
+        Grid::parse_with(s, |c| c.encode_utf8(&mut buf).parse::<T>())
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row_idx in 0..self.num_rows() {
+            if row_idx > 0 {
+                writeln!(f)?;
+            }
+            for item in self.row_iter(row_idx) {
+                write!(f, "{}", item)?;
+            }
         }
+
+        Ok(())
     }
 }
 
@@ -124,10 +407,9 @@ mod tests {
 
         let grid = input.parse::<Grid<u8>>().unwrap();
 
-        assert_eq!(
-            grid.items,
-            vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]]
-        );
+        assert_eq!(grid.items, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(grid.num_rows(), 3);
+        assert_eq!(grid.num_columns(), 3);
     }
 
     #[test]
@@ -139,6 +421,23 @@ mod tests {
         assert_eq!(grid.to_row_col(5), (1, 2));
     }
 
+    #[test]
+    fn to_row_col_rectangular() {
+        let wide = Grid::new(vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8]]);
+
+        assert_eq!(wide.to_row_col(3), (0, 3));
+        assert_eq!(wide.to_row_col(4), (1, 0));
+        assert_eq!(wide.to_row_col(7), (1, 3));
+        assert_eq!(wide.to_idx(1, 2), 6);
+
+        let tall = Grid::new(vec![vec![1, 2], vec![3, 4], vec![5, 6], vec![7, 8]]);
+
+        assert_eq!(tall.to_row_col(3), (1, 1));
+        assert_eq!(tall.to_row_col(4), (2, 0));
+        assert_eq!(tall.to_row_col(7), (3, 1));
+        assert_eq!(tall[(3, 0)], 7);
+    }
+
     #[test]
     fn grid_iter() {
         let items = vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]];
@@ -170,4 +469,144 @@ mod tests {
         assert_eq!(col_iter.next(), Some(&8));
         assert_eq!(col_iter.next(), None);
     }
+
+    #[test]
+    fn row_and_col_iter_rectangular() {
+        let grid = Grid::new(vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8]]);
+
+        assert_eq!(grid.row_iter(1).collect::<Vec<_>>(), vec![&5, &6, &7, &8]);
+        assert_eq!(grid.col_iter(3).collect::<Vec<_>>(), vec![&4, &8]);
+    }
+
+    #[test]
+    fn enumerate_positions() {
+        let grid = Grid::new(vec![vec!['a', 'b', 'c'], vec!['d', 'e', 'f']]);
+
+        assert_eq!(
+            grid.enumerate_positions().collect::<Vec<_>>(),
+            vec![
+                ((0, 0), &'a'),
+                ((0, 1), &'b'),
+                ((0, 2), &'c'),
+                ((1, 0), &'d'),
+                ((1, 1), &'e'),
+                ((1, 2), &'f'),
+            ]
+        );
+    }
+
+    #[test]
+    fn get_out_of_bounds() {
+        let grid = Grid::new(vec![vec![1, 2, 3], vec![4, 5, 6]]);
+
+        assert_eq!(grid.get(1, 2), Some(&6));
+        assert_eq!(grid.get(0, 3), None);
+        assert_eq!(grid.get(2, 0), None);
+    }
+
+    #[test]
+    fn parse_with_letters() {
+        let grid = Grid::parse_with("Sab\ncdE", |c| match c {
+            'S' => Ok(0),
+            'E' => Ok(25),
+            'a'..='z' => Ok(c as u8 - b'a'),
+            _ => Err(c),
+        })
+        .unwrap();
+
+        assert_eq!(grid.items, vec![0, 0, 1, 2, 3, 25]);
+        assert_eq!(grid.num_columns(), 3);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            "123\n4x6".parse::<Grid<u8>>().unwrap_err().to_string(),
+            "invalid cell at row 1, column 1: invalid digit found in string"
+        );
+        assert_eq!(
+            "123\n45".parse::<Grid<u8>>(),
+            Err(ParseGridError::RaggedRow {
+                row: 1,
+                expected: 3,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn parse_words_with() {
+        let grid = Grid::parse_words_with("10 200\n3 45", |word| word.parse::<u32>()).unwrap();
+
+        assert_eq!(grid.items, vec![10, 200, 3, 45]);
+        assert_eq!(grid.num_rows(), 2);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let input = "\
+30373
+25512";
+        let grid = input.parse::<Grid<u8>>().unwrap();
+
+        assert_eq!(grid.to_string(), input);
+        assert_eq!(
+            grid.render_with(|height| if *height >= 5 { '#' } else { '.' }),
+            "...#.\n.##.."
+        );
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = Grid::new(vec![vec![1, 2, 3], vec![4, 5, 6]]);
+
+        assert_eq!(
+            grid.neighbours((0, 0)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0)]
+        );
+        assert_eq!(
+            grid.neighbours((1, 1)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0), (1, 2)]
+        );
+        assert_eq!(
+            grid.all_neighbours((1, 2)).collect::<Vec<_>>(),
+            vec![(0, 1), (0, 2), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn transpose_and_rotate() {
+        let grid = Grid::new(vec![vec![1, 2, 3], vec![4, 5, 6]]);
+
+        assert_eq!(
+            grid.transpose(),
+            Grid::new(vec![vec![1, 4], vec![2, 5], vec![3, 6]])
+        );
+        assert_eq!(
+            grid.rotate_clockwise(),
+            Grid::new(vec![vec![4, 1], vec![5, 2], vec![6, 3]])
+        );
+        assert_eq!(
+            grid.rotate_counter_clockwise(),
+            Grid::new(vec![vec![3, 6], vec![2, 5], vec![1, 4]])
+        );
+        assert_eq!(grid.rotate_clockwise().rotate_counter_clockwise(), grid);
+    }
+
+    #[test]
+    fn view() {
+        let grid = Grid::new(vec![
+            vec![1, 2, 3, 4],
+            vec![5, 6, 7, 8],
+            vec![9, 10, 11, 12],
+        ]);
+
+        let view = grid.view((1, 1), 2, 2);
+
+        assert_eq!(view.get(0, 0), Some(&6));
+        assert_eq!(view.get(1, 1), Some(&11));
+        assert_eq!(view.get(0, 2), None);
+        assert_eq!(view.row(1), &[10, 11]);
+        assert_eq!(view.to_grid(), Grid::new(vec![vec![6, 7], vec![10, 11]]));
+    }
 }
@@ -1,32 +1,44 @@
 use grid::Grid;
+use neighbourhood::{scenic_scores_in, visible_trees_in, Neighbourhood};
 
 pub mod grid;
+pub mod neighbourhood;
+pub mod placement;
 
 pub fn count_visible(input: &str) -> usize {
     let grid = input.parse::<Grid<u8>>().unwrap();
 
-    grid.iter()
-        .enumerate()
-        .filter(|(idx, _)| grid.is_visible(*idx))
+    visible_trees(&grid)
+        .iter()
+        .filter(|is_visible| **is_visible)
         .count()
 }
 
-pub fn max_scenic_score(input: &str) -> u32 {
+pub fn max_scenic_score(input: &str) -> u64 {
     let grid = input.parse::<Grid<u8>>().unwrap();
 
-    grid.iter()
-        .enumerate()
-        .map(|(idx, _)| grid.scenic_score(idx))
-        .max()
-        .unwrap()
+    *scenic_scores(&grid).iter().max().unwrap()
 }
 
-trait Visible {
+/// Computes whether each tree is visible from outside the grid along a row or
+/// column.
+pub fn visible_trees(grid: &Grid<u8>) -> Grid<bool> {
+    visible_trees_in(grid, &Neighbourhood::cardinal())
+}
+
+/// Computes the scenic score of every tree, looking along its row and column.
+pub fn scenic_scores(grid: &Grid<u8>) -> Grid<u64> {
+    scenic_scores_in(grid, &Neighbourhood::cardinal())
+}
+
+/// Per-tree visibility queries. Prefer `visible_trees` and `scenic_scores` when
+/// every tree is needed.
+pub trait Visible {
     fn is_visible(&self, idx: usize) -> bool;
     fn scenic_score(&self, idx: usize) -> u32;
 }
 
-impl<'a> Visible for Grid<'a, u8> {
+impl Visible for Grid<u8> {
     fn is_visible(&self, idx: usize) -> bool {
         let (row_idx, col_idx) = self.to_row_col(idx);
 
@@ -38,7 +50,7 @@ impl<'a> Visible for Grid<'a, u8> {
             return true;
         }
 
-        let height = self.items[row_idx][col_idx];
+        let height = self[(row_idx, col_idx)];
 
         let row: Vec<_> = self.row_iter(row_idx).collect();
         let left = &row[..col_idx];
@@ -77,7 +89,7 @@ impl<'a> Visible for Grid<'a, u8> {
 
     fn scenic_score(&self, idx: usize) -> u32 {
         let (row_idx, col_idx) = self.to_row_col(idx);
-        let height = self.items[row_idx][col_idx];
+        let height = self[(row_idx, col_idx)];
 
         let row: Vec<_> = self.row_iter(row_idx).collect();
 
@@ -154,6 +166,67 @@ mod tests {
         assert_eq!(grid.scenic_score(idx), expected);
     }
 
+    #[test]
+    fn visible_trees_matches_per_tree_check() {
+        let input =
+            fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
+        let grid = input.parse::<Grid<u8>>().unwrap();
+
+        let visible = visible_trees(&grid);
+
+        for idx in 0..grid.len() {
+            assert_eq!(visible.items[idx], grid.is_visible(idx), "tree {}", idx);
+        }
+    }
+
+    #[test]
+    fn scenic_scores_match_per_tree_score() {
+        let input =
+            fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
+        let grid = input.parse::<Grid<u8>>().unwrap();
+
+        let scores = scenic_scores(&grid);
+
+        for idx in 0..grid.len() {
+            assert_eq!(
+                scores.items[idx],
+                u64::from(grid.scenic_score(idx)),
+                "tree {}",
+                idx
+            );
+        }
+        assert_eq!(scores[(1, 2)], 4);
+        assert_eq!(scores[(3, 2)], 8);
+    }
+
+    #[test]
+    fn rectangular_forest() {
+        let grid = "\
+393
+215
+654
+393"
+        .parse::<Grid<u8>>()
+        .unwrap();
+
+        let visible = visible_trees(&grid);
+        let scores = scenic_scores(&grid);
+
+        for idx in 0..grid.len() {
+            assert_eq!(visible.items[idx], grid.is_visible(idx), "tree {}", idx);
+            assert_eq!(
+                scores.items[idx],
+                u64::from(grid.scenic_score(idx)),
+                "tree {}",
+                idx
+            );
+        }
+        assert!(!visible[(1, 1)]);
+        assert!(visible[(2, 1)]);
+        assert_eq!(scores[(1, 1)], 1);
+        assert_eq!(scores[(2, 1)], 2);
+    }
+
     #[test]
     fn max_scenic_score_example() {
         let input =
@@ -0,0 +1,332 @@
+use crate::grid::Grid;
+
+/// A direction to look in from a tree, as a step in rows and columns. Every
+/// `k`-th multiple of the step from the tree lies on the ray.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ray {
+    pub d_row: isize,
+    pub d_col: isize,
+}
+
+impl Ray {
+    pub const UP: Ray = Ray::new(-1, 0);
+    pub const DOWN: Ray = Ray::new(1, 0);
+    pub const LEFT: Ray = Ray::new(0, -1);
+    pub const RIGHT: Ray = Ray::new(0, 1);
+    pub const UP_LEFT: Ray = Ray::new(-1, -1);
+    pub const UP_RIGHT: Ray = Ray::new(-1, 1);
+    pub const DOWN_LEFT: Ray = Ray::new(1, -1);
+    pub const DOWN_RIGHT: Ray = Ray::new(1, 1);
+
+    pub const fn new(d_row: isize, d_col: isize) -> Self {
+        Ray { d_row, d_col }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neighbourhood {
+    rays: Vec<Ray>,
+}
+
+impl Neighbourhood {
+    pub fn cardinal() -> Self {
+        Neighbourhood::from_rays(vec![Ray::UP, Ray::LEFT, Ray::RIGHT, Ray::DOWN])
+    }
+
+    pub fn eight_way() -> Self {
+        Neighbourhood::from_rays(vec![
+            Ray::UP_LEFT,
+            Ray::UP,
+            Ray::UP_RIGHT,
+            Ray::LEFT,
+            Ray::RIGHT,
+            Ray::DOWN_LEFT,
+            Ray::DOWN,
+            Ray::DOWN_RIGHT,
+        ])
+    }
+
+    pub fn from_rays(rays: Vec<Ray>) -> Self {
+        assert!(
+            rays.iter().all(|ray| *ray != Ray::new(0, 0)),
+            "Rays should have a non-zero step"
+        );
+
+        Neighbourhood { rays }
+    }
+
+    pub fn rays(&self) -> &[Ray] {
+        &self.rays
+    }
+}
+
+fn step(grid: &Grid<u8>, (row_idx, col_idx): (usize, usize), ray: Ray) -> Option<(usize, usize)> {
+    let row_idx = row_idx.checked_add_signed(ray.d_row)?;
+    let col_idx = col_idx.checked_add_signed(ray.d_col)?;
+
+    if row_idx < grid.num_rows() && col_idx < grid.num_columns() {
+        Some((row_idx, col_idx))
+    } else {
+        None
+    }
+}
+
+/// Splits the grid into the lines of trees that lie along `ray`. Each line
+/// starts at the edge the ray leaves the grid through and runs backwards, so
+/// every tree comes after all the trees it would look over along `ray`.
+fn lines_along(grid: &Grid<u8>, ray: Ray) -> Vec<Vec<usize>> {
+    let backwards = Ray::new(-ray.d_row, -ray.d_col);
+
+    grid.enumerate_positions()
+        .map(|(position, _)| position)
+        .filter(|position| step(grid, *position, ray).is_none())
+        .map(|start| {
+            std::iter::successors(Some(start), |position| step(grid, *position, backwards))
+                .map(|(row_idx, col_idx)| grid.to_idx(row_idx, col_idx))
+                .collect()
+        })
+        .collect()
+}
+
+/// Computes whether each tree can be seen from outside the grid along any of
+/// the neighbourhood's rays, using a running-max sweep per line.
+pub fn visible_trees_in(grid: &Grid<u8>, neighbourhood: &Neighbourhood) -> Grid<bool> {
+    let mut visible = vec![false; grid.len()];
+
+    for ray in neighbourhood.rays() {
+        for line in lines_along(grid, *ray) {
+            let mut tallest: Option<u8> = None;
+            for idx in line {
+                let height = grid.items[idx];
+                if tallest.is_none_or(|tallest| height > tallest) {
+                    visible[idx] = true;
+                    tallest = Some(height);
+                }
+            }
+        }
+    }
+
+    Grid::from_vec(visible, grid.num_columns())
+}
+
+/// Computes how many steps each tree can see along `ray` before its view is
+/// blocked by a tree at least as tall, or it reaches the edge.
+pub fn viewing_distances(grid: &Grid<u8>, ray: Ray) -> Grid<u32> {
+    let mut distances = vec![0; grid.len()];
+
+    for line in lines_along(grid, ray) {
+        // Positions in `line` of trees that no later tree has been taller than.
+        let mut stack: Vec<usize> = vec![];
+        for (pos, idx) in line.iter().enumerate() {
+            let height = grid.items[*idx];
+            while stack
+                .last()
+                .is_some_and(|top| grid.items[line[*top]] < height)
+            {
+                stack.pop();
+            }
+
+            distances[*idx] = match stack.last() {
+                Some(blocker) => pos - blocker,
+                None => pos,
+            } as u32;
+
+            stack.push(pos);
+        }
+    }
+
+    Grid::from_vec(distances, grid.num_columns())
+}
+
+/// Multiplies together each tree's viewing distances along the neighbourhood's
+/// rays, saturating at `u64::MAX`.
+pub fn scenic_scores_in(grid: &Grid<u8>, neighbourhood: &Neighbourhood) -> Grid<u64> {
+    let mut scores = vec![1; grid.len()];
+
+    for ray in neighbourhood.rays() {
+        for (score, distance) in scores.iter_mut().zip(viewing_distances(grid, *ray).iter()) {
+            *score = scenic_score([*score, u64::from(*distance)]);
+        }
+    }
+
+    Grid::from_vec(scores, grid.num_columns())
+}
+
+/// The product of `distances`, saturating at `u64::MAX` rather than
+/// overflowing when there are many rays.
+pub(crate) fn scenic_score(distances: impl IntoIterator<Item = u64>) -> u64 {
+    distances.into_iter().fold(1, u64::saturating_mul)
+}
+
+/// Computes which trees can be seen from `observer`, a `(row, col)` position
+/// outside the grid. A tree is visible if every tree on the lattice points
+/// between it and the observer is shorter.
+pub fn visible_from(grid: &Grid<u8>, observer: (isize, isize)) -> Grid<bool> {
+    let (observer_row, observer_col) = observer;
+    assert!(
+        observer_row < 0
+            || observer_col < 0
+            || observer_row >= grid.num_rows() as isize
+            || observer_col >= grid.num_columns() as isize,
+        "The observer should be outside the grid"
+    );
+
+    // For each tree, the steps towards the observer and how many of them there are.
+    let mut sight_lines: Vec<_> = grid
+        .enumerate_positions()
+        .map(|((row_idx, col_idx), _)| {
+            let d_row = observer_row - row_idx as isize;
+            let d_col = observer_col - col_idx as isize;
+            let num_steps = gcd(d_row.unsigned_abs(), d_col.unsigned_abs());
+            let ray = Ray::new(d_row / num_steps as isize, d_col / num_steps as isize);
+            (num_steps, (row_idx, col_idx), ray)
+        })
+        .collect();
+
+    // The tallest tree between each tree and the observer. Trees closer to the
+    // observer along a sight line are always handled first.
+    sight_lines.sort_by_key(|(num_steps, _, _)| *num_steps);
+    let mut tallest_before: Vec<Option<u8>> = vec![None; grid.len()];
+    let mut visible = vec![false; grid.len()];
+
+    for (_, position, ray) in sight_lines {
+        let idx = grid.to_idx(position.0, position.1);
+
+        if let Some((row_idx, col_idx)) = step(grid, position, ray) {
+            let closer = grid.to_idx(row_idx, col_idx);
+            tallest_before[idx] = tallest_before[closer].max(Some(grid.items[closer]));
+        }
+
+        visible[idx] = tallest_before[idx].is_none_or(|tallest| grid.items[idx] > tallest);
+    }
+
+    Grid::from_vec(visible, grid.num_columns())
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::Visible;
+
+    fn example() -> Grid<u8> {
+        let input =
+            fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
+
+        input.parse::<Grid<u8>>().unwrap()
+    }
+
+    #[test]
+    fn cardinal_matches_per_tree_check() {
+        let grid = example();
+
+        let visible = visible_trees_in(&grid, &Neighbourhood::cardinal());
+        let scores = scenic_scores_in(&grid, &Neighbourhood::cardinal());
+
+        for idx in 0..grid.len() {
+            assert_eq!(visible.items[idx], grid.is_visible(idx), "tree {}", idx);
+            assert_eq!(
+                scores.items[idx],
+                u64::from(grid.scenic_score(idx)),
+                "tree {}",
+                idx
+            );
+        }
+    }
+
+    #[test]
+    fn eight_way() {
+        let grid = example();
+
+        let visible = visible_trees_in(&grid, &Neighbourhood::eight_way());
+
+        assert_eq!(visible.iter().filter(|v| **v).count(), 22);
+        // The 4 can't be seen along its row or column, but can from the top right.
+        assert!(!visible_trees_in(&grid, &Neighbourhood::cardinal())[(3, 3)]);
+        assert!(visible[(3, 3)]);
+
+        // The middle 3 sees 1 tree in every direction except up and to the right.
+        let scores = scenic_scores_in(&grid, &Neighbourhood::eight_way());
+        assert_eq!(viewing_distances(&grid, Ray::UP_RIGHT)[(2, 2)], 2);
+        assert_eq!(scores[(2, 2)], 2);
+    }
+
+    /// A tall tree in the middle of a 33 by 33 grid of short ones, which can see
+    /// 16 trees in each of the eight directions.
+    fn open_clearing() -> Grid<u8> {
+        let mut heights = vec![0; 33 * 33];
+        heights[16 * 33 + 16] = 9;
+        Grid::from_vec(heights, 33)
+    }
+
+    #[test]
+    fn scores_past_u32() {
+        let scores = scenic_scores_in(&open_clearing(), &Neighbourhood::eight_way());
+
+        assert_eq!(scores[(16, 16)], 16u64.pow(8));
+        assert_eq!(scenic_score([u64::MAX, 2]), u64::MAX);
+    }
+
+    #[test]
+    fn knight_ray() {
+        let grid = example();
+
+        let distances = viewing_distances(&grid, Ray::new(1, 2));
+
+        assert_eq!(distances[(0, 0)], 1);
+        assert_eq!(distances[(1, 1)], 1);
+        assert_eq!(distances[(3, 0)], 1);
+        assert_eq!(distances[(4, 0)], 0);
+        assert_eq!(distances[(0, 3)], 0);
+    }
+
+    #[test]
+    fn edge_observer_matches_cardinal_direction() {
+        let grid = example();
+
+        let from_left = visible_from(&grid, (2, -1));
+
+        assert_eq!(
+            from_left.row_iter(2).copied().collect::<Vec<_>>(),
+            vec![true, false, false, false, false]
+        );
+    }
+
+    #[test]
+    fn corner_observer() {
+        let grid = "\
+12
+34"
+        .parse::<Grid<u8>>()
+        .unwrap();
+
+        let visible = visible_from(&grid, (-1, -1));
+
+        assert_eq!(visible.items, vec![true, true, true, true]);
+
+        let grid = "\
+31
+12"
+        .parse::<Grid<u8>>()
+        .unwrap();
+
+        let visible = visible_from(&grid, (-1, -1));
+
+        assert_eq!(visible.items, vec![true, true, true, false]);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside the grid")]
+    fn observer_inside_grid() {
+        visible_from(&example(), (1, 1));
+    }
+}
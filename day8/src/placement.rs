@@ -0,0 +1,225 @@
+use crate::{
+    grid::Grid,
+    neighbourhood::{scenic_score, viewing_distances, Neighbourhood, Ray},
+};
+
+/// An inclusive rectangle of `(row, col)` positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub top_left: (usize, usize),
+    pub bottom_right: (usize, usize),
+}
+
+impl Region {
+    fn contains(&self, (row_idx, col_idx): (usize, usize)) -> bool {
+        (self.top_left.0..=self.bottom_right.0).contains(&row_idx)
+            && (self.top_left.1..=self.bottom_right.1).contains(&col_idx)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlacementFilter {
+    pub min_height: Option<u8>,
+    /// The furthest a tree may be from its nearest edge, where edge trees are 0.
+    pub max_edge_distance: Option<usize>,
+    pub region: Option<Region>,
+}
+
+impl PlacementFilter {
+    fn allows(&self, grid: &Grid<u8>, position: (usize, usize)) -> bool {
+        let (row_idx, col_idx) = position;
+        let edge_distance = row_idx
+            .min(col_idx)
+            .min(grid.num_rows() - 1 - row_idx)
+            .min(grid.num_columns() - 1 - col_idx);
+
+        self.min_height
+            .is_none_or(|min_height| grid[position] >= min_height)
+            && self
+                .max_edge_distance
+                .is_none_or(|max_edge_distance| edge_distance <= max_edge_distance)
+            && self.region.is_none_or(|region| region.contains(position))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placement {
+    pub position: (usize, usize),
+    pub height: u8,
+    pub scenic_score: u64,
+    /// How far the tree can see along each of the neighbourhood's rays, whose
+    /// product is the scenic score, saturating at `u64::MAX`.
+    pub viewing_distances: Vec<(Ray, u32)>,
+}
+
+/// Returns up to `k` of the trees allowed by `filter` with the highest scenic
+/// scores, best first. Ties are broken by position in row-major order.
+pub fn top_placements(
+    grid: &Grid<u8>,
+    neighbourhood: &Neighbourhood,
+    filter: &PlacementFilter,
+    k: usize,
+) -> Vec<Placement> {
+    let distances: Vec<_> = neighbourhood
+        .rays()
+        .iter()
+        .map(|ray| (*ray, viewing_distances(grid, *ray)))
+        .collect();
+
+    let mut placements: Vec<_> = grid
+        .enumerate_positions()
+        .filter(|(position, _)| filter.allows(grid, *position))
+        .map(|(position, height)| {
+            let viewing_distances: Vec<_> = distances
+                .iter()
+                .map(|(ray, distances)| (*ray, distances[position]))
+                .collect();
+
+            Placement {
+                position,
+                height: *height,
+                scenic_score: scenic_score(
+                    viewing_distances
+                        .iter()
+                        .map(|(_, distance)| u64::from(*distance)),
+                ),
+                viewing_distances,
+            }
+        })
+        .collect();
+
+    placements.sort_by(|a, b| {
+        b.scenic_score
+            .cmp(&a.scenic_score)
+            .then(a.position.cmp(&b.position))
+    });
+    placements.truncate(k);
+
+    placements
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn example() -> Grid<u8> {
+        let input =
+            fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
+
+        input.parse::<Grid<u8>>().unwrap()
+    }
+
+    #[test]
+    fn best_placement_explains_score() {
+        let placements = top_placements(
+            &example(),
+            &Neighbourhood::cardinal(),
+            &PlacementFilter::default(),
+            1,
+        );
+
+        assert_eq!(
+            placements,
+            vec![Placement {
+                position: (3, 2),
+                height: 5,
+                scenic_score: 8,
+                viewing_distances: vec![
+                    (Ray::UP, 2),
+                    (Ray::LEFT, 2),
+                    (Ray::RIGHT, 2),
+                    (Ray::DOWN, 1)
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn scores_past_u32() {
+        let mut heights = vec![0; 33 * 33];
+        heights[16 * 33 + 16] = 9;
+        let grid = Grid::from_vec(heights, 33);
+
+        let placements = top_placements(
+            &grid,
+            &Neighbourhood::eight_way(),
+            &PlacementFilter::default(),
+            1,
+        );
+
+        assert_eq!(placements[0].position, (16, 16));
+        assert_eq!(placements[0].scenic_score, 16u64.pow(8));
+    }
+
+    #[test]
+    fn top_k() {
+        let placements = top_placements(
+            &example(),
+            &Neighbourhood::cardinal(),
+            &PlacementFilter::default(),
+            3,
+        );
+
+        let summary: Vec<_> = placements
+            .iter()
+            .map(|p| (p.position, p.scenic_score))
+            .collect();
+
+        assert_eq!(summary, vec![((3, 2), 8), ((2, 1), 6), ((1, 2), 4)]);
+    }
+
+    #[test]
+    fn filters() {
+        let grid = example();
+        let neighbourhood = Neighbourhood::cardinal();
+
+        let tall = PlacementFilter {
+            min_height: Some(6),
+            ..Default::default()
+        };
+        let positions: Vec<_> = top_placements(&grid, &neighbourhood, &tall, 10)
+            .iter()
+            .map(|p| p.position)
+            .collect();
+        assert_eq!(positions, vec![(0, 3), (2, 0), (3, 4), (4, 3)]);
+
+        let near_edge = PlacementFilter {
+            max_edge_distance: Some(0),
+            ..Default::default()
+        };
+        assert!(top_placements(&grid, &neighbourhood, &near_edge, 25)
+            .iter()
+            .all(|p| p.scenic_score == 0));
+        assert_eq!(
+            top_placements(&grid, &neighbourhood, &near_edge, 25).len(),
+            16
+        );
+
+        let region = PlacementFilter {
+            region: Some(Region {
+                top_left: (0, 0),
+                bottom_right: (1, 4),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            top_placements(&grid, &neighbourhood, &region, 1)[0].position,
+            (1, 2)
+        );
+    }
+
+    #[test]
+    fn empty_when_nothing_matches() {
+        let filter = PlacementFilter {
+            min_height: Some(10),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            top_placements(&example(), &Neighbourhood::cardinal(), &filter, 3),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::{Duration, Instant};
+
+use day9::{Command, RopeSimulation};
+
+fn time(name: &str, f: impl FnOnce() -> usize) -> Duration {
+    let start = Instant::now();
+    let visited = f();
+    let elapsed = start.elapsed();
+
+    println!(
+        "{:<40} {:>10.2?} ({} cells visited)",
+        name, elapsed, visited
+    );
+
+    elapsed
+}
+
+fn main() {
+    for num_steps in [10_000, 100_000, 1_000_000] {
+        let moves = vec![
+            (Command::Right, num_steps),
+            (Command::Up, num_steps),
+            (Command::Left, num_steps),
+            (Command::Down, num_steps),
+        ];
+        let commands: Vec<_> = moves
+            .iter()
+            .flat_map(|(command, num)| vec![command.clone(); *num])
+            .collect();
+
+        let stepped = time(&format!("{} steps per command, stepped", num_steps), || {
+            let mut sim = RopeSimulation::new(10);
+            sim.apply_commands(&commands);
+            sim.visited_tail_positions.len()
+        });
+
+        let fast = time(
+            &format!("{} steps per command, run-length", num_steps),
+            || {
+                let mut sim = RopeSimulation::new(10);
+                sim.apply_moves(&moves);
+                sim.visited_tail_positions.len()
+            },
+        );
+
+        println!(
+            "{:<40} {:>10.1}x",
+            "speed-up",
+            stepped.as_secs_f64() / fast.as_secs_f64()
+        );
+    }
+}
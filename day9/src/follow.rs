@@ -0,0 +1,162 @@
+use crate::Point;
+
+/// Decides how a knot moves after the knot in front of it, its leader, has
+/// moved. Rules should only depend on the knots' relative positions.
+pub trait FollowRule<const N: usize = 2> {
+    /// Where `follower` ends up after its leader moved from `previous_leader`
+    /// to `leader`.
+    fn follow(
+        &self,
+        previous_leader: &Point<N>,
+        leader: &Point<N>,
+        follower: &Point<N>,
+    ) -> Point<N>;
+}
+
+/// The puzzle's rule: a knot stays put while it touches its leader, including
+/// diagonally, and otherwise takes one step towards it along every axis they
+/// differ in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Chebyshev;
+
+impl<const N: usize> FollowRule<N> for Chebyshev {
+    fn follow(
+        &self,
+        previous_leader: &Point<N>,
+        leader: &Point<N>,
+        follower: &Point<N>,
+    ) -> Point<N> {
+        Slack { max_distance: 1 }.follow(previous_leader, leader, follower)
+    }
+}
+
+/// Each knot moves into the place its leader has just left, like the body of
+/// a snake.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Snake;
+
+impl<const N: usize> FollowRule<N> for Snake {
+    fn follow(
+        &self,
+        previous_leader: &Point<N>,
+        _leader: &Point<N>,
+        _follower: &Point<N>,
+    ) -> Point<N> {
+        previous_leader.clone()
+    }
+}
+
+/// A knot stays put until its leader is more than `max_distance` away along
+/// any axis, and then takes one step towards it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slack {
+    pub max_distance: i128,
+}
+
+impl<const N: usize> FollowRule<N> for Slack {
+    fn follow(
+        &self,
+        _previous_leader: &Point<N>,
+        leader: &Point<N>,
+        follower: &Point<N>,
+    ) -> Point<N> {
+        if leader.chebyshev_distance(follower) <= self.max_distance {
+            return follower.clone();
+        }
+
+        follower.clone() + (leader.clone() - follower.clone()).signum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{parse_input, parse_moves, Command, RopeSimulation};
+
+    #[test]
+    fn diagonal_commands() {
+        let mut sim = RopeSimulation::new(2);
+
+        sim.apply_commands(&parse_input("UR 3\nDR 1"));
+
+        assert_eq!(sim.points(), &[Point::new(4, 2), Point::new(3, 2)]);
+        assert_eq!(
+            sim.visited_tail_positions,
+            HashSet::from([
+                Point::new(0, 0),
+                Point::new(1, 1),
+                Point::new(2, 2),
+                Point::new(3, 2)
+            ])
+        );
+    }
+
+    #[test]
+    fn snake() {
+        let mut sim = RopeSimulation::new(3).with_follow_rule(Snake);
+
+        sim.apply_commands(&[Command::Right, Command::Right, Command::Right]);
+        assert_eq!(
+            sim.points(),
+            &[Point::new(3, 0), Point::new(2, 0), Point::new(1, 0)]
+        );
+
+        sim.apply_commands(&[Command::Up]);
+        assert_eq!(
+            sim.points(),
+            &[Point::new(3, 1), Point::new(3, 0), Point::new(2, 0)]
+        );
+    }
+
+    #[test]
+    fn slack() {
+        let mut sim = RopeSimulation::new(2).with_follow_rule(Slack { max_distance: 2 });
+
+        sim.apply_commands(&parse_input("R 2\nU 1"));
+        assert_eq!(sim.points(), &[Point::new(2, 1), Point::new(0, 0)]);
+
+        sim.apply_commands(&parse_input("R 1"));
+        assert_eq!(sim.points(), &[Point::new(3, 1), Point::new(1, 1)]);
+    }
+
+    #[test]
+    fn slack_of_one_is_chebyshev() {
+        let input = "R 5\nUL 8\nL 8\nD 3\nDR 17\nD 10\nL 25\nUR 20";
+
+        let mut chebyshev = RopeSimulation::new(10);
+        chebyshev.apply_commands(&parse_input(input));
+
+        let mut slack = RopeSimulation::new(10).with_follow_rule(Slack { max_distance: 1 });
+        slack.apply_commands(&parse_input(input));
+
+        assert_eq!(chebyshev.points(), slack.points());
+        assert_eq!(
+            chebyshev.visited_tail_positions,
+            slack.visited_tail_positions
+        );
+    }
+
+    #[test]
+    fn moves_match_single_steps_for_every_rule() {
+        let input = "R 5\nUL 8\nL 8\nD 3\nDR 17\nD 10\nL 25\nUR 20";
+        let rules: Vec<fn() -> Box<dyn FollowRule>> =
+            vec![|| Box::new(Chebyshev), || Box::new(Snake), || {
+                Box::new(Slack { max_distance: 3 })
+            }];
+
+        for rule in rules {
+            let mut stepped = RopeSimulation::new(10);
+            stepped.follow_rule = rule();
+            stepped.apply_commands(&parse_input(input));
+
+            let mut fast = RopeSimulation::new(10);
+            fast.follow_rule = rule();
+            fast.apply_moves(&parse_moves(input));
+
+            assert_eq!(stepped.points(), fast.points());
+            assert_eq!(stepped.visited_tail_positions, fast.visited_tail_positions);
+        }
+    }
+}
@@ -1,15 +1,31 @@
 use std::{
     collections::HashSet,
+    mem,
     ops::{Add, Sub},
     str::FromStr,
 };
 
+use follow::{Chebyshev, FollowRule};
+
+pub mod follow;
+pub mod render;
+
 #[derive(Debug, Clone)]
 pub enum Command {
     Up,
     Down,
     Left,
     Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+    /// Along the third axis, towards positive `z`.
+    Forward,
+    Backward,
+    /// One step towards the positive end of the given axis, counting from 0.
+    Positive(usize),
+    Negative(usize),
 }
 
 impl FromStr for Command {
@@ -21,140 +37,358 @@ impl FromStr for Command {
             "D" => Ok(Command::Down),
             "L" => Ok(Command::Left),
             "R" => Ok(Command::Right),
-            _ => Err(()),
+            "UL" => Ok(Command::UpLeft),
+            "UR" => Ok(Command::UpRight),
+            "DL" => Ok(Command::DownLeft),
+            "DR" => Ok(Command::DownRight),
+            "F" => Ok(Command::Forward),
+            "B" => Ok(Command::Backward),
+            _ => {
+                if let Some(axis) = s.strip_prefix('+') {
+                    axis.parse().map(Command::Positive).map_err(|_| ())
+                } else if let Some(axis) = s.strip_prefix('-') {
+                    axis.parse().map(Command::Negative).map_err(|_| ())
+                } else {
+                    Err(())
+                }
+            }
+        }
+    }
+}
+
+impl Command {
+    /// The axes the command moves the head along, and in which direction.
+    fn steps(&self) -> Vec<(usize, i128)> {
+        match self {
+            Command::Up => vec![(1, 1)],
+            Command::Down => vec![(1, -1)],
+            Command::Left => vec![(0, -1)],
+            Command::Right => vec![(0, 1)],
+            Command::UpLeft => vec![(0, -1), (1, 1)],
+            Command::UpRight => vec![(0, 1), (1, 1)],
+            Command::DownLeft => vec![(0, -1), (1, -1)],
+            Command::DownRight => vec![(0, 1), (1, -1)],
+            Command::Forward => vec![(2, 1)],
+            Command::Backward => vec![(2, -1)],
+            Command::Positive(axis) => vec![(*axis, 1)],
+            Command::Negative(axis) => vec![(*axis, -1)],
+        }
+    }
+
+    /// How one step of the command moves the head.
+    fn delta<const N: usize>(&self) -> Point<N> {
+        let mut coords = [0; N];
+
+        for (axis, step) in self.steps() {
+            assert!(
+                axis < N,
+                "{:?} can't move a point with {} dimensions",
+                self,
+                N
+            );
+            coords[axis] += step;
         }
+
+        Point::from(coords)
     }
 }
 
 pub fn parse_input(input: &str) -> Vec<Command> {
+    parse_moves(input)
+        .into_iter()
+        .flat_map(|(command, num)| vec![command; num])
+        .collect()
+}
+
+/// Parses each line into its command and the number of steps to repeat it for.
+pub fn parse_moves(input: &str) -> Vec<(Command, usize)> {
     input
         .lines()
-        .flat_map(|line| {
+        .map(|line| {
             let split: Vec<_> = line.split_whitespace().collect();
             let command = split[0].parse::<Command>().unwrap();
             let num = split[1].parse::<usize>().unwrap();
 
-            vec![command; num]
+            (command, num)
         })
         .collect()
 }
 
+/// A point on an `N`-dimensional lattice, 2D unless stated otherwise.
 #[derive(Debug, Eq, Hash, PartialEq, Clone)]
-pub struct Point {
-    x: i128,
-    y: i128,
+pub struct Point<const N: usize = 2> {
+    coords: [i128; N],
 }
 
+pub type Point3 = Point<3>;
+
 impl Point {
-    fn new(x: i128, y: i128) -> Point {
-        Point { x, y }
+    pub fn new(x: i128, y: i128) -> Point {
+        Point { coords: [x, y] }
+    }
+
+    pub fn x(&self) -> i128 {
+        self.coords[0]
+    }
+
+    pub fn y(&self) -> i128 {
+        self.coords[1]
+    }
+}
+
+impl<const N: usize> Point<N> {
+    pub fn origin() -> Self {
+        Point { coords: [0; N] }
+    }
+
+    pub fn coords(&self) -> &[i128; N] {
+        &self.coords
+    }
+
+    pub fn chebyshev_distance(&self, other: &Point<N>) -> i128 {
+        self.coords
+            .iter()
+            .zip(other.coords.iter())
+            .map(|(a, b)| (a - b).abs())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The point with each coordinate replaced by its sign.
+    pub fn signum(&self) -> Self {
+        Point {
+            coords: self.coords.map(i128::signum),
+        }
+    }
+
+    fn scale(&self, factor: i128) -> Self {
+        Point {
+            coords: self.coords.map(|coord| coord * factor),
+        }
+    }
+}
+
+impl<const N: usize> From<[i128; N]> for Point<N> {
+    fn from(coords: [i128; N]) -> Self {
+        Point { coords }
     }
 }
 
-impl Add for Point {
+impl<const N: usize> Add for Point<N> {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Point {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
+        let mut coords = self.coords;
+        for (coord, other) in coords.iter_mut().zip(rhs.coords.iter()) {
+            *coord += other;
         }
+
+        Point { coords }
     }
 }
 
-impl Sub for Point {
+impl<const N: usize> Sub for Point<N> {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self::Output {
-        Self {
-            x: self.x - other.x,
-            y: self.y - other.y,
+        let mut coords = self.coords;
+        for (coord, other) in coords.iter_mut().zip(other.coords.iter()) {
+            *coord -= other;
         }
+
+        Point { coords }
     }
 }
 
-pub struct RopeSimulation {
-    points: Vec<Point>,
-    pub visited_tail_positions: HashSet<Point>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnotHistory<const N: usize = 2> {
+    pub visited: HashSet<Point<N>>,
+    /// The knot's position before the first step and after every step since.
+    pub trajectory: Vec<Point<N>>,
+    /// The number of the first step that moved the knot, counting from 1 at the
+    /// first step after tracking started.
+    pub first_moved_at: Option<usize>,
+    /// The furthest the knot has been from where it started, as a Chebyshev distance.
+    pub max_displacement: i128,
+}
+
+impl<const N: usize> KnotHistory<N> {
+    fn new(start: &Point<N>) -> Self {
+        KnotHistory {
+            visited: HashSet::from([start.clone()]),
+            trajectory: vec![start.clone()],
+            first_moved_at: None,
+            max_displacement: 0,
+        }
+    }
+
+    fn record(&mut self, point: &Point<N>) {
+        let start = &self.trajectory[0];
+
+        if self.first_moved_at.is_none() && point != start {
+            self.first_moved_at = Some(self.trajectory.len());
+        }
+        self.max_displacement = self.max_displacement.max(point.chebyshev_distance(start));
+
+        self.visited.insert(point.clone());
+        self.trajectory.push(point.clone());
+    }
+
+    pub fn distinct_cells(&self) -> usize {
+        self.visited.len()
+    }
+}
+
+/// A rope of knots on an `N`-dimensional lattice, 2D unless stated otherwise.
+pub struct RopeSimulation<const N: usize = 2> {
+    points: Vec<Point<N>>,
+    start: Point<N>,
+    knot_histories: Option<Vec<KnotHistory<N>>>,
+    follow_rule: Box<dyn FollowRule<N>>,
+    pub visited_tail_positions: HashSet<Point<N>>,
 }
 
 impl RopeSimulation {
-    pub fn from(points: Vec<Point>) -> RopeSimulation {
-        let mut visited_tail_positions: HashSet<Point> = HashSet::new();
+    pub fn new(num_points: usize) -> RopeSimulation {
+        RopeSimulation::at_origin(num_points)
+    }
+}
+
+impl<const N: usize> RopeSimulation<N> {
+    pub fn from(points: Vec<Point<N>>) -> RopeSimulation<N> {
+        let mut visited_tail_positions: HashSet<Point<N>> = HashSet::new();
         visited_tail_positions.insert(points.last().unwrap().clone());
 
         RopeSimulation {
+            start: points[0].clone(),
             points,
+            knot_histories: None,
+            follow_rule: Box::new(Chebyshev),
             visited_tail_positions,
         }
     }
 
-    pub fn new(num_points: usize) -> RopeSimulation {
-        let origin = Point::new(0, 0);
-        let mut visited_tail_positions = HashSet::new();
-        visited_tail_positions.insert(origin.clone());
+    /// A rope of `num_points` knots all starting at the origin.
+    pub fn at_origin(num_points: usize) -> RopeSimulation<N> {
+        RopeSimulation::from(vec![Point::origin(); num_points])
+    }
 
-        let mut points = vec![];
+    /// Replaces the default rule for how each knot follows the one in front.
+    pub fn with_follow_rule(mut self, follow_rule: impl FollowRule<N> + 'static) -> Self {
+        self.follow_rule = Box::new(follow_rule);
+        self
+    }
 
-        for _ in 0..num_points {
-            points.push(origin.clone());
-        }
+    /// The knots' positions, from the head to the tail.
+    pub fn points(&self) -> &[Point<N>] {
+        &self.points
+    }
 
-        RopeSimulation {
-            points,
-            visited_tail_positions,
-        }
+    /// Where the head started.
+    pub fn start(&self) -> &Point<N> {
+        &self.start
+    }
+
+    /// Starts recording the history of every knot, not just the tail's visited
+    /// positions, from the knots' current positions onwards.
+    pub fn track_all_knots(&mut self) {
+        self.knot_histories = Some(self.points.iter().map(KnotHistory::new).collect());
+    }
+
+    /// The history of each knot from the head to the tail, if `track_all_knots`
+    /// has been called.
+    pub fn knot_histories(&self) -> Option<&[KnotHistory<N>]> {
+        self.knot_histories.as_deref()
     }
 
     pub fn apply_commands(&mut self, commands: &[Command]) {
         for command in commands.iter() {
-            self.apply_command(command);
-            self.visited_tail_positions
-                .insert(self.points.last().unwrap().clone());
+            self.step(&command.delta());
         }
     }
 
-    fn apply_command(&mut self, command: &Command) {
-        match command {
-            Command::Up => self.points[0].y += 1,
-            Command::Down => self.points[0].y -= 1,
-            Command::Left => self.points[0].x -= 1,
-            Command::Right => self.points[0].x += 1,
-        };
+    /// Moves the head by `delta`, lets the rest of the rope follow and records
+    /// where the knots ended up.
+    fn step(&mut self, delta: &Point<N>) {
+        self.move_rope(delta);
+        self.visited_tail_positions
+            .insert(self.points.last().unwrap().clone());
 
-        let indices = &(0..self.points.len()).collect::<Vec<usize>>();
-
-        for i in indices.windows(2) {
-            let prev = i[0];
-            let next = i[1];
-            self.update_tail_pos(prev, next);
+        if let Some(histories) = self.knot_histories.as_mut() {
+            for (history, point) in histories.iter_mut().zip(self.points.iter()) {
+                history.record(point);
+            }
         }
     }
 
-    fn update_tail_pos(&mut self, prev: usize, next: usize) {
-        let prev = &self.points[prev].clone();
-        let next = &mut self.points[next];
+    /// Runs each command for its number of steps. Once the rope has settled into
+    /// a shape that the follow rule just moves along with the head, the rest of
+    /// the command is applied in one go.
+    pub fn apply_moves(&mut self, moves: &[(Command, usize)]) {
+        for (command, num) in moves.iter() {
+            let delta = command.delta();
+            let mut remaining = *num;
+
+            while remaining > 0 && !self.is_steady_along(&delta) {
+                self.step(&delta);
+                remaining -= 1;
+            }
 
-        let diff = prev.clone() - next.clone();
+            if remaining > 0 {
+                self.fast_forward(&delta, remaining);
+            }
+        }
+    }
 
-        let (x_diff, y_diff) = (diff.x, diff.y);
+    /// Whether moving the head by `delta` would move every knot by the same step.
+    /// Follow rules only look at relative positions, so every later step would
+    /// too.
+    fn is_steady_along(&self, delta: &Point<N>) -> bool {
+        self.points.windows(2).all(|pair| {
+            let (leader, follower) = (&pair[0], &pair[1]);
+            self.follow_rule
+                .follow(leader, &(leader.clone() + delta.clone()), follower)
+                == follower.clone() + delta.clone()
+        })
+    }
 
-        match (x_diff, y_diff) {
-            // Same position
-            (0, 0) => (),
-            // Directly above or below
-            (0, y_diff) if y_diff.abs() == 2 => {
-                next.y += y_diff.signum();
-            }
-            // Directly left or right
-            (x_diff, 0) if x_diff.abs() == 2 => {
-                next.x += x_diff.signum();
+    /// Moves the whole rope `num` steps of `delta`, which is only the same as
+    /// stepping it one at a time when the rope is steady.
+    fn fast_forward(&mut self, delta: &Point<N>, num: usize) {
+        if let Some(histories) = self.knot_histories.as_mut() {
+            for (history, point) in histories.iter_mut().zip(self.points.iter()) {
+                let mut point = point.clone();
+                for _ in 0..num {
+                    point = point + delta.clone();
+                    history.record(&point);
+                }
             }
-            (x_diff, y_diff) if x_diff.abs() > 1 || y_diff.abs() > 1 => {
-                next.y += y_diff.signum();
-                next.x += x_diff.signum();
-            }
-            _ => (),
+        }
+
+        let mut tail = self.points.last().unwrap().clone();
+        self.visited_tail_positions.reserve(num);
+        for _ in 0..num {
+            tail = tail + delta.clone();
+            self.visited_tail_positions.insert(tail.clone());
+        }
+
+        let offset = delta.scale(num as i128);
+        for point in self.points.iter_mut() {
+            *point = point.clone() + offset.clone();
+        }
+    }
+
+    fn move_rope(&mut self, delta: &Point<N>) {
+        let mut previous_leader = self.points[0].clone();
+        self.points[0] = previous_leader.clone() + delta.clone();
+
+        for next in 1..self.points.len() {
+            let moved = self.follow_rule.follow(
+                &previous_leader,
+                &self.points[next - 1],
+                &self.points[next],
+            );
+            previous_leader = mem::replace(&mut self.points[next], moved);
         }
     }
 }
@@ -178,7 +412,7 @@ mod tests {
     #[test]
     fn diagonal_example_up() {
         let mut sim = RopeSimulation::from(vec![Point::new(2, 2), Point::new(1, 1)]);
-        sim.apply_command(&Command::Up);
+        sim.apply_commands(&[Command::Up]);
 
         assert_eq!(sim.points.first().unwrap(), &Point::new(2, 3));
         assert_eq!(sim.points.last().unwrap(), &Point::new(2, 2));
@@ -187,7 +421,7 @@ mod tests {
     #[test]
     fn diagonal_example_right() {
         let mut sim = RopeSimulation::from(vec![Point::new(2, 2), Point::new(1, 1)]);
-        sim.apply_command(&Command::Right);
+        sim.apply_commands(&[Command::Right]);
 
         assert_eq!(sim.points.first().unwrap(), &Point::new(3, 2));
         assert_eq!(sim.points.last().unwrap(), &Point::new(2, 2));
@@ -196,12 +430,193 @@ mod tests {
     #[test]
     fn tail_doesnt_move() {
         let mut sim = RopeSimulation::from(vec![Point::new(4, 0), Point::new(3, 0)]);
-        sim.apply_command(&Command::Up);
+        sim.apply_commands(&[Command::Up]);
 
         assert_eq!(sim.points.first().unwrap(), &Point::new(4, 1));
         assert_eq!(sim.points.last().unwrap(), &Point::new(3, 0));
     }
 
+    #[test]
+    fn knot_histories() {
+        let input =
+            fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
+        let commands = parse_input(&input);
+
+        let mut sim = RopeSimulation::new(10);
+        assert_eq!(sim.knot_histories(), None);
+
+        sim.track_all_knots();
+        sim.apply_commands(&commands);
+
+        let histories = sim.knot_histories().unwrap();
+        let distinct_cells: Vec<_> = histories.iter().map(|h| h.distinct_cells()).collect();
+        let first_moved_at: Vec<_> = histories.iter().map(|h| h.first_moved_at).collect();
+
+        assert_eq!(histories.len(), 10);
+        assert_eq!(histories[0].trajectory.len(), commands.len() + 1);
+        assert_eq!(histories[0].trajectory[4], Point::new(4, 0));
+        assert_eq!(histories[0].max_displacement, 5);
+        assert_eq!(distinct_cells, vec![21, 13, 7, 4, 3, 2, 1, 1, 1, 1]);
+        assert_eq!(
+            first_moved_at,
+            vec![
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(4),
+                Some(6),
+                Some(8),
+                None,
+                None,
+                None,
+                None
+            ]
+        );
+        assert_eq!(histories[9].visited, sim.visited_tail_positions);
+    }
+
+    #[test]
+    fn first_moved_at_counts_from_tracking() {
+        let mut sim = RopeSimulation::new(3);
+        sim.apply_moves(&[(Command::Right, 5)]);
+
+        sim.track_all_knots();
+        sim.apply_moves(&[(Command::Up, 3)]);
+
+        let first_moved_at: Vec<_> = sim
+            .knot_histories()
+            .unwrap()
+            .iter()
+            .map(|h| h.first_moved_at)
+            .collect();
+        assert_eq!(first_moved_at, vec![Some(1), Some(2), Some(2)]);
+    }
+
+    #[test]
+    fn knot_histories_match_per_length_simulations() {
+        let input =
+            fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
+        let commands = parse_input(&input);
+
+        let mut sim = RopeSimulation::new(10);
+        sim.track_all_knots();
+        sim.apply_commands(&commands);
+
+        for num_points in 2..=10 {
+            let mut shorter = RopeSimulation::new(num_points);
+            shorter.apply_commands(&commands);
+
+            assert_eq!(
+                sim.knot_histories().unwrap()[num_points - 1].visited,
+                shorter.visited_tail_positions
+            );
+        }
+    }
+
+    fn assert_same_state(a: &RopeSimulation, b: &RopeSimulation) {
+        assert_eq!(a.points, b.points);
+        assert_eq!(a.visited_tail_positions, b.visited_tail_positions);
+        assert_eq!(a.knot_histories, b.knot_histories);
+    }
+
+    #[test]
+    fn moves_match_single_steps() {
+        let input = "R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20";
+        let moves = parse_moves(input);
+
+        for num_points in [1, 2, 10] {
+            let mut stepped = RopeSimulation::new(num_points);
+            stepped.track_all_knots();
+            stepped.apply_commands(&parse_input(input));
+
+            let mut fast = RopeSimulation::new(num_points);
+            fast.track_all_knots();
+            fast.apply_moves(&moves);
+
+            assert_same_state(&stepped, &fast);
+        }
+
+        let mut sim = RopeSimulation::new(10);
+        sim.apply_moves(&moves);
+        assert_eq!(sim.visited_tail_positions.len(), 36);
+    }
+
+    #[test]
+    fn moves_fast_forward_long_commands() {
+        let moves = vec![(Command::Left, 1_000_000), (Command::Up, 3)];
+
+        let mut sim = RopeSimulation::new(10);
+        sim.apply_moves(&moves);
+
+        assert_eq!(sim.points[0], Point::new(-1_000_000, 3));
+        assert_eq!(sim.points[9], Point::new(-999_992, 1));
+        assert_eq!(sim.visited_tail_positions.len(), 999_993);
+    }
+
+    #[test]
+    fn three_dimensions() {
+        let mut sim = RopeSimulation::<3>::at_origin(2);
+
+        sim.apply_commands(&parse_input("F 2\nU 1\nR 1\nF 1"));
+
+        assert_eq!(
+            sim.points(),
+            &[Point3::from([1, 1, 3]), Point3::from([1, 1, 2])]
+        );
+        assert_eq!(
+            sim.visited_tail_positions,
+            HashSet::from([
+                Point3::from([0, 0, 0]),
+                Point3::from([0, 0, 1]),
+                Point3::from([1, 1, 2])
+            ])
+        );
+    }
+
+    #[test]
+    fn three_dimensions_in_a_plane_match_two() {
+        let input =
+            fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
+        let commands = parse_input(&input);
+
+        for num_points in [2, 10] {
+            let mut flat = RopeSimulation::new(num_points);
+            flat.apply_commands(&commands);
+
+            let mut sim = RopeSimulation::<3>::at_origin(num_points);
+            sim.apply_moves(&parse_moves(&input));
+
+            let projected: HashSet<_> = sim
+                .visited_tail_positions
+                .iter()
+                .map(|point| {
+                    let [x, y, z] = *point.coords();
+                    assert_eq!(z, 0);
+                    Point::new(x, y)
+                })
+                .collect();
+
+            assert_eq!(projected, flat.visited_tail_positions);
+        }
+    }
+
+    #[test]
+    fn any_axis() {
+        let moves = parse_moves("+3 4\n-0 2\n+1 1");
+
+        let mut sim = RopeSimulation::<4>::at_origin(3);
+        sim.apply_moves(&moves);
+
+        assert_eq!(sim.points()[0], Point::from([-2, 1, 0, 4]));
+        assert_eq!(sim.points()[2], Point::from([-1, 0, 0, 3]));
+    }
+
+    #[test]
+    #[should_panic(expected = "can't move a point with 2 dimensions")]
+    fn axis_out_of_range() {
+        RopeSimulation::new(2).apply_commands(&[Command::Forward]);
+    }
+
     #[test]
     fn example() {
         let input =
@@ -1,14 +1,14 @@
 use std::fs;
 
-use day9::{parse_input, RopeSimulation};
+use day9::{parse_moves, RopeSimulation};
 
 fn main() {
     let input = fs::read_to_string("input.txt").expect("Should have been able to read the file");
-    let commands = parse_input(&input);
+    let moves = parse_moves(&input);
 
     let mut sim = RopeSimulation::new(2);
 
-    sim.apply_commands(&commands);
+    sim.apply_moves(&moves);
 
     println!(
         "For a 2 point rope,the number of positions that the tail of the rope visits at least once is: {}",
@@ -17,7 +17,7 @@ fn main() {
 
     let mut sim = RopeSimulation::new(10);
 
-    sim.apply_commands(&commands);
+    sim.apply_moves(&moves);
 
     println!(
         "For a 10 point rope, the number of positions that the tail of the rope visits at least once is: {}",
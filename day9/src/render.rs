@@ -0,0 +1,246 @@
+use std::slice;
+
+use crate::{Command, Point, RopeSimulation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    EachStep,
+    EachCommand,
+    End,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub show_knots: bool,
+    pub show_visited: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            show_knots: true,
+            show_visited: false,
+        }
+    }
+}
+
+/// Draws a simulation in the puzzle's notation: `H` for the head, `1`..`9` for
+/// the knots behind it (or `T` when there is only one), `s` for the start, `#`
+/// for cells the tail has visited and `.` elsewhere. Knots nearer the head are
+/// drawn over those behind them.
+///
+/// The drawn area only ever grows, so that successive frames line up.
+pub struct Renderer {
+    options: RenderOptions,
+    min: Option<Point>,
+    max: Option<Point>,
+}
+
+impl Renderer {
+    pub fn new(options: RenderOptions) -> Self {
+        Renderer {
+            options,
+            min: None,
+            max: None,
+        }
+    }
+
+    fn include(&mut self, point: &Point) {
+        self.min = Some(match &self.min {
+            Some(min) => Point::new(min.x().min(point.x()), min.y().min(point.y())),
+            None => point.clone(),
+        });
+        self.max = Some(match &self.max {
+            Some(max) => Point::new(max.x().max(point.x()), max.y().max(point.y())),
+            None => point.clone(),
+        });
+    }
+
+    pub fn render(&mut self, sim: &RopeSimulation) -> String {
+        self.include(sim.start());
+        for point in sim.points() {
+            self.include(point);
+        }
+        for point in sim.visited_tail_positions.iter() {
+            self.include(point);
+        }
+
+        let (min, max) = (self.min.clone().unwrap(), self.max.clone().unwrap());
+        let mut output = String::new();
+
+        for y in (min.y()..=max.y()).rev() {
+            for x in min.x()..=max.x() {
+                output.push(self.cell(sim, &Point::new(x, y)));
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+
+    fn cell(&self, sim: &RopeSimulation, point: &Point) -> char {
+        if self.options.show_knots {
+            if let Some(idx) = sim.points().iter().position(|knot| knot == point) {
+                return knot_label(idx, sim.points().len());
+            }
+        }
+
+        if point == sim.start() {
+            's'
+        } else if self.options.show_visited && sim.visited_tail_positions.contains(point) {
+            '#'
+        } else {
+            '.'
+        }
+    }
+}
+
+/// Knots past 9 are all drawn as `*`, since letters would clash with `H`, `T`
+/// and the `s` start marker.
+fn knot_label(idx: usize, num_knots: usize) -> char {
+    match idx {
+        0 => 'H',
+        1 if num_knots == 2 => 'T',
+        1..=9 => char::from_digit(idx as u32, 10).unwrap(),
+        _ => '*',
+    }
+}
+
+/// Runs `moves` on `sim`, returning a frame after every step, after every
+/// command, or only once at the end, depending on `mode`.
+pub fn render_simulation(
+    sim: &mut RopeSimulation,
+    moves: &[(Command, usize)],
+    mode: RenderMode,
+    options: RenderOptions,
+) -> Vec<String> {
+    let mut renderer = Renderer::new(options);
+    let mut frames = vec![];
+
+    for (command, num) in moves.iter() {
+        for _ in 0..*num {
+            sim.apply_commands(slice::from_ref(command));
+            if mode == RenderMode::EachStep {
+                frames.push(renderer.render(sim));
+            }
+        }
+
+        if mode == RenderMode::EachCommand {
+            frames.push(renderer.render(sim));
+        }
+    }
+
+    if mode == RenderMode::End || frames.is_empty() {
+        frames.push(renderer.render(sim));
+    }
+
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::parse_moves;
+
+    fn example_moves() -> Vec<(Command, usize)> {
+        let input =
+            fs::read_to_string("test_input.txt").expect("Should have been able to read the file");
+
+        parse_moves(&input)
+    }
+
+    #[test]
+    fn end_state() {
+        let mut sim = RopeSimulation::new(2);
+
+        let frames = render_simulation(
+            &mut sim,
+            &example_moves(),
+            RenderMode::End,
+            RenderOptions::default(),
+        );
+
+        let expected = "\
+.....
+.....
+.TH..
+.....
+s....
+";
+
+        assert_eq!(frames, vec![expected]);
+    }
+
+    #[test]
+    fn long_rope_labels() {
+        let labels: String = [0, 1, 9, 10, 28]
+            .iter()
+            .map(|idx| knot_label(*idx, 30))
+            .collect();
+
+        assert_eq!(labels, "H19**");
+        assert_eq!(knot_label(1, 2), 'T');
+    }
+
+    #[test]
+    fn visited_positions() {
+        let mut sim = RopeSimulation::new(2);
+        let options = RenderOptions {
+            show_knots: false,
+            show_visited: true,
+        };
+
+        let frames = render_simulation(&mut sim, &example_moves(), RenderMode::End, options);
+
+        let expected = "\
+..##.
+...##
+.####
+....#
+s###.
+";
+
+        assert_eq!(frames, vec![expected]);
+    }
+
+    #[test]
+    fn each_step_grows_bounding_box() {
+        let mut sim = RopeSimulation::new(2);
+
+        let frames = render_simulation(
+            &mut sim,
+            &[(Command::Right, 2), (Command::Up, 1)],
+            RenderMode::EachStep,
+            RenderOptions::default(),
+        );
+
+        assert_eq!(frames, vec!["TH\n", "sTH\n", "..H\nsT.\n"]);
+    }
+
+    #[test]
+    fn each_command_with_ten_knots() {
+        let mut sim = RopeSimulation::new(10);
+
+        let frames = render_simulation(
+            &mut sim,
+            &[(Command::Right, 4), (Command::Up, 4)],
+            RenderMode::EachCommand,
+            RenderOptions::default(),
+        );
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], "4321H\n");
+
+        let expected = "\
+....H
+....1
+..432
+.5...
+6....
+";
+
+        assert_eq!(frames[1], expected);
+    }
+}